@@ -1,4 +1,6 @@
 // Advent of Code 2022 - Day 4
+use std::fmt;
+use std::io::{IsTerminal, stdout};
 use std::ops::Range;
 
 const INPUT: &str = include_str!("./input.txt");
@@ -41,6 +43,124 @@ impl Overlaps for Range<u32> {
     }
 }
 
+/// Relation between the two section assignments of a pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Classification {
+    /// The assignments share no sections and leave a gap between them.
+    Disjoint,
+    /// The assignments share no sections, but one starts right after the other ends.
+    Touching,
+    /// The assignments share some, but not all, sections.
+    Overlapping,
+    /// One assignment fully contains the other.
+    Contained,
+    /// Both assignments cover exactly the same sections.
+    Identical,
+}
+
+impl Classification {
+    fn classify(first: &Range<u32>, second: &Range<u32>) -> Self {
+        if first == second {
+            Classification::Identical
+        } else if first.contains_or_contained(second) {
+            Classification::Contained
+        } else if first.overlaps(second) {
+            Classification::Overlapping
+        } else if first.end.checked_add(1) == Some(second.start)
+            || second.end.checked_add(1) == Some(first.start)
+        {
+            Classification::Touching
+        } else {
+            Classification::Disjoint
+        }
+    }
+}
+
+impl fmt::Display for Classification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Classification::Disjoint => "disjoint",
+            Classification::Touching => "touching",
+            Classification::Overlapping => "overlapping",
+            Classification::Contained => "contained",
+            Classification::Identical => "identical",
+        };
+        f.pad(name)
+    }
+}
+
+/// Formats a range the same way as the puzzle input, e.g. `2-4`.
+fn format_range(range: &Range<u32>) -> String {
+    format!("{}-{}", range.start, range.end)
+}
+
+/// Draws the sections 1 up to and including `width` as the puzzle does, e.g. `.234.....`.
+/// Sections are labelled by their last digit, sections outside the range are drawn as `.`.
+/// When `highlight` is set, sections that are also part of `other` are colored.
+fn render_range(range: &Range<u32>, other: &Range<u32>, width: u32, highlight: bool) -> String {
+    let mut line = String::new();
+    for section in 1..=width {
+        if section < range.start || section > range.end {
+            line.push('.');
+            continue;
+        }
+        let label = char::from_digit(section % 10, 10).expect("Digit should be below 10");
+        if highlight && section >= other.start && section <= other.end {
+            line.push_str(&format!("\x1b[1;33m{label}\x1b[0m"));
+        } else {
+            line.push(label);
+        }
+    }
+    line
+}
+
+/// Renders every assignment pair on two lines, followed by the classification of the pair.
+fn render_pairs(input: &[(Range<u32>, Range<u32>)], highlight: bool) -> String {
+    let width = input
+        .iter()
+        .map(|(first, second)| first.end.max(second.end))
+        .max()
+        .unwrap_or(0);
+    let mut output = String::new();
+    for (first, second) in input {
+        let classification = Classification::classify(first, second);
+        output.push_str(&format!(
+            "{}  {}\n{}  {}  {classification}\n\n",
+            render_range(first, second, width, highlight),
+            format_range(first),
+            render_range(second, first, width, highlight),
+            format_range(second),
+        ));
+    }
+    output
+}
+
+/// Builds a table with the classification of each assignment pair by line number.
+fn classification_table(input: &[(Range<u32>, Range<u32>)]) -> String {
+    let mut table = format!(
+        "{:>5}  {:<9}  {:<9}  {}\n",
+        "line", "first", "second", "classification"
+    );
+    for (line, (first, second)) in input.iter().enumerate() {
+        table.push_str(&format!(
+            "{:>5}  {:<9}  {:<9}  {}\n",
+            line + 1,
+            format_range(first),
+            format_range(second),
+            Classification::classify(first, second),
+        ));
+    }
+    table
+}
+
+fn parse_input(input: &str) -> Vec<(Range<u32>, Range<u32>)> {
+    input
+        .lines()
+        .map(|l| l.split_once(',').expect("Lines should contain a single ,"))
+        .map(|(l, r)| (parse_into_range(l), parse_into_range(r)))
+        .collect()
+}
+
 fn solve_part_one(input: &[(Range<u32>, Range<u32>)]) -> usize {
     input
         .iter()
@@ -53,14 +173,81 @@ fn solve_part_two(input: &[(Range<u32>, Range<u32>)]) -> usize {
 }
 
 fn main() {
-    let input: Vec<(Range<u32>, Range<u32>)> = INPUT
-        .lines()
-        .map(|l| l.split_once(',').expect("Lines should contain a single ,"))
-        .map(|(l, r)| (parse_into_range(l), parse_into_range(r)))
-        .collect();
-    dbg!(&input);
+    let input = parse_input(INPUT);
+    // Optional diagnostics: `render` draws each pair, `table` lists the classification per line.
+    match std::env::args().nth(1).as_deref() {
+        Some("render") => print!("{}", render_pairs(&input, stdout().is_terminal())),
+        Some("table") => print!("{}", classification_table(&input)),
+        _ => {}
+    }
     let answer_part_one = solve_part_one(&input);
-    println!("Total number of assignment pairs where one range fully contain the other: {answer_part_one}");
+    println!(
+        "Total number of assignment pairs where one range fully contain the other: {answer_part_one}"
+    );
     let answer_part_two = solve_part_two(&input);
     println!("Total number of assignment pairs where ranges overlap: {answer_part_two}");
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::Classification;
+
+    const EXAMPLE_INPUT: &str = "2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8";
+
+    #[test]
+    fn part1() {
+        let input = crate::parse_input(EXAMPLE_INPUT);
+        assert_eq!(crate::solve_part_one(&input), 2);
+    }
+
+    #[test]
+    fn part2() {
+        let input = crate::parse_input(EXAMPLE_INPUT);
+        assert_eq!(crate::solve_part_two(&input), 4);
+    }
+
+    #[test]
+    fn classification() {
+        let input = crate::parse_input(EXAMPLE_INPUT);
+        let classifications: Vec<_> = input
+            .iter()
+            .map(|(first, second)| Classification::classify(first, second))
+            .collect();
+        assert_eq!(
+            classifications,
+            vec![
+                Classification::Disjoint,
+                Classification::Touching,
+                Classification::Overlapping,
+                Classification::Contained,
+                Classification::Contained,
+                Classification::Overlapping,
+            ]
+        );
+        assert_eq!(
+            Classification::classify(&(3..5), &(3..5)),
+            Classification::Identical
+        );
+        // Sections at the very end of the range of IDs must not overflow.
+        assert_eq!(
+            Classification::classify(&(1..u32::MAX - 1), &(u32::MAX..u32::MAX)),
+            Classification::Touching
+        );
+        assert_eq!(
+            Classification::classify(&(u32::MAX..u32::MAX), &(1..3)),
+            Classification::Disjoint
+        );
+    }
+
+    #[test]
+    fn render() {
+        let input = crate::parse_input("2-4,6-8");
+        let rendered = crate::render_pairs(&input, false);
+        assert_eq!(rendered, ".234....  2-4\n.....678  6-8  disjoint\n\n");
+    }
+}
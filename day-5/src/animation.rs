@@ -0,0 +1,210 @@
+//! Terminal animation of a crane carrying out the rearrangement procedure.
+
+use std::io::{self, Write};
+use std::slice;
+use std::thread;
+use std::time::Duration;
+
+use crate::Ship;
+use crate::crane::Crane;
+use crate::procedure::{self, Step};
+
+/// Crates off the deck, like the load hanging from the crane, drawn at a column offset and a height above the deck.
+#[derive(Clone, Copy)]
+struct Load<'a> {
+    labels: &'a [String],
+    /// Position of the first character of the load within a line.
+    offset: usize,
+    /// Level of the bottom crate of the load, level 0 being the deck.
+    level: usize,
+}
+
+/// Animates a procedure on a terminal, one frame for each position of the crane.
+pub struct Animation<'a> {
+    crane: &'a dyn Crane,
+    delay: Duration,
+    /// Widths are fixed for the whole animation, so columns do not shift while crates move.
+    widths: Vec<usize>,
+    /// Number of crates on the ship.
+    crates: usize,
+    /// Number of crate rows in each frame, enough to lift any load over the highest stack,
+    /// and for bottom-loading cranes also to hold the target stack up above that load.
+    height: usize,
+}
+
+impl<'a> Animation<'a> {
+    /// Creates an animation for the given ship, showing `fps` frames per second (0 disables waiting).
+    pub fn new(ship: &Ship, crane: &'a dyn Crane, fps: u32) -> Self {
+        let label_width = ship
+            .stacks
+            .iter()
+            .flatten()
+            .map(|l| l.chars().count())
+            .max()
+            .unwrap_or(1);
+        let widths = (1..=ship.stacks.len())
+            .map(|number| label_width.max(number.to_string().len()) + 2)
+            .collect();
+        let delay = match fps {
+            0 => Duration::ZERO,
+            fps => Duration::from_secs(1) / fps,
+        };
+        let crates = ship.stacks.iter().map(Vec::len).sum::<usize>();
+        let height = if crane.loads_from_bottom() {
+            2 * crates + 1
+        } else {
+            crates + 1
+        };
+        Self {
+            crane,
+            delay,
+            widths,
+            crates,
+            height,
+        }
+    }
+
+    /// Position of the first character of the column of the stack at idx (0-based).
+    fn offset(&self, idx: usize) -> usize {
+        self.widths[..idx].iter().sum::<usize>() + idx
+    }
+
+    /// Draws the ship with crates that are off the deck, such as the load hanging from the crane.
+    fn frame(&self, ship: &Ship, loads: &[Load]) -> String {
+        let mut lines = ship.draw(&self.widths, self.height);
+        for load in loads {
+            for (idx, label) in load.labels.iter().enumerate() {
+                let line = &mut lines[self.height - 1 - load.level - idx];
+                let mut chars: Vec<char> = line.chars().collect();
+                let drawn = format!("[{label}]");
+                let end = load.offset + drawn.chars().count();
+                if chars.len() < end {
+                    chars.resize(end, ' ');
+                }
+                chars.splice(load.offset..end, drawn.chars());
+                *line = chars.into_iter().collect();
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// Clears the terminal and shows a single frame with a status line below it.
+    fn show(&self, out: &mut impl Write, frame: &str, status: &str) -> io::Result<()> {
+        write!(out, "\x1b[H\x1b[2J{frame}\n\n{status}\n")?;
+        out.flush()?;
+        thread::sleep(self.delay);
+        Ok(())
+    }
+
+    /// Animates the whole procedure, lifting, moving and dropping every load of the crane,
+    /// and ends with a summary frame. Returns the ship at the end of the procedure.
+    pub fn run(&self, mut ship: Ship, steps: &[Step], out: &mut impl Write) -> io::Result<Ship> {
+        procedure::dry_run(&ship, steps)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        let mut lifts = 0;
+        self.show(out, &self.frame(&ship, &[]), "Starting stacks")?;
+        for (number, step) in steps.iter().enumerate() {
+            let status = format!(
+                "Step {}/{}: move {} from {} to {}",
+                number + 1,
+                steps.len(),
+                step.count,
+                step.from,
+                step.to
+            );
+            for lift in self.crane.lifts(step.count).into_iter().filter(|&n| n > 0) {
+                lifts += 1;
+                // Take the crates off the source stack and hang them from the crane.
+                let mut deck = ship.clone();
+                let source = &mut deck.stacks[step.from - 1];
+                let labels = source.split_off(source.len() - lift);
+                let source_level = deck.stacks[step.from - 1].len();
+                let target_level = deck.stacks[step.to - 1].len();
+                // Level at which the load clears every stack left on the deck.
+                let top = self.crates + 1 - lift;
+                let (start, end) = (self.offset(step.from - 1), self.offset(step.to - 1));
+                let mut load = Load {
+                    labels: &labels,
+                    offset: start,
+                    level: source_level,
+                };
+                // Lifting
+                for level in source_level..=top {
+                    load.level = level;
+                    self.show(out, &self.frame(&deck, slice::from_ref(&load)), &status)?;
+                }
+                if self.crane.loads_from_bottom() {
+                    // The target stack is held up above the load, which then goes down to the deck
+                    // before the stack is lowered onto it. A load taken from the target stack itself
+                    // first moves aside, so the stack can go up past it.
+                    if start == end {
+                        let aside = end + self.widths[step.to - 1] + 1;
+                        for offset in path(start, aside) {
+                            load.offset = offset;
+                            self.show(out, &self.frame(&deck, slice::from_ref(&load)), &status)?;
+                        }
+                    }
+                    let stack = std::mem::take(&mut deck.stacks[step.to - 1]);
+                    let mut raised = Load {
+                        labels: &stack,
+                        offset: end,
+                        level: 0,
+                    };
+                    for level in 0..=self.crates + 1 {
+                        raised.level = level;
+                        self.show(out, &self.frame(&deck, &[raised, load]), &status)?;
+                    }
+                    for offset in path(load.offset, end) {
+                        load.offset = offset;
+                        self.show(out, &self.frame(&deck, &[raised, load]), &status)?;
+                    }
+                    for level in (0..=top).rev() {
+                        load.level = level;
+                        self.show(out, &self.frame(&deck, &[raised, load]), &status)?;
+                    }
+                    for level in (lift..=self.crates + 1).rev() {
+                        raised.level = level;
+                        self.show(out, &self.frame(&deck, &[raised, load]), &status)?;
+                    }
+                } else {
+                    // Moving
+                    for offset in path(start, end) {
+                        load.offset = offset;
+                        self.show(out, &self.frame(&deck, slice::from_ref(&load)), &status)?;
+                    }
+                    // Dropping
+                    for level in (target_level..=top).rev() {
+                        load.level = level;
+                        self.show(out, &self.frame(&deck, slice::from_ref(&load)), &status)?;
+                    }
+                }
+                let lift_step = Step {
+                    count: lift,
+                    ..*step
+                };
+                self.crane
+                    .move_crates(&mut ship, &lift_step)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+            }
+            self.show(out, &self.frame(&ship, &[]), &status)?;
+        }
+        let summary = format!(
+            "Crane {} moved {} crates in {} steps using {lifts} lifts.\nThe top crates create the message: {}",
+            self.crane.name(),
+            steps.iter().map(|step| step.count).sum::<usize>(),
+            steps.len(),
+            ship.get_message()
+        );
+        self.show(out, &self.frame(&ship, &[]), &summary)?;
+        Ok(ship)
+    }
+}
+
+/// Column offsets passed when moving from `start` to `end`, both included.
+fn path(start: usize, end: usize) -> Vec<usize> {
+    if start <= end {
+        (start..=end).collect()
+    } else {
+        (end..=start).rev().collect()
+    }
+}
@@ -0,0 +1,121 @@
+//! Crane models that can carry out the steps of a rearrangement procedure.
+
+use crate::Ship;
+use crate::procedure::{self, ProcedureError, Step};
+
+/// A crane that moves crates between the stacks of a `Ship`.
+pub trait Crane {
+    /// Name of the crane model, as accepted by `from_name`.
+    fn name(&self) -> String;
+
+    /// Carries out a step of the procedure. Steps that cannot be carried out are reported
+    /// without touching the ship.
+    fn move_crates(&self, ship: &mut Ship, step: &Step) -> Result<(), ProcedureError>;
+
+    /// Sizes of the consecutive lifts the crane uses to move `n` crates.
+    /// Moving each lift on its own should give the same result as moving all `n` crates at once.
+    fn lifts(&self, n: usize) -> Vec<usize> {
+        vec![n]
+    }
+
+    /// Whether the crane slides its load under the destination stack instead of on top of it.
+    fn loads_from_bottom(&self) -> bool {
+        false
+    }
+}
+
+/// The CrateMover 9000 moves crates one at a time.
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "9000".to_string()
+    }
+
+    fn move_crates(&self, ship: &mut Ship, step: &Step) -> Result<(), ProcedureError> {
+        ship.move_crates(step)
+    }
+
+    fn lifts(&self, n: usize) -> Vec<usize> {
+        vec![1; n]
+    }
+}
+
+/// The CrateMover 9001 moves multiple crates at once, retaining their order.
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        "9001".to_string()
+    }
+
+    fn move_crates(&self, ship: &mut Ship, step: &Step) -> Result<(), ProcedureError> {
+        ship.move_stack_of_crates(step)
+    }
+}
+
+/// A crane that lifts at most `capacity` crates at once.
+/// Larger moves are split into several lifts, each retaining the order of its crates.
+pub struct CappedCrane {
+    capacity: usize,
+}
+
+impl Crane for CappedCrane {
+    fn name(&self) -> String {
+        format!("capped-{}", self.capacity)
+    }
+
+    fn move_crates(&self, ship: &mut Ship, step: &Step) -> Result<(), ProcedureError> {
+        // Check the whole step first, so a failing step does not leave some lifts done.
+        procedure::check(ship, step)?;
+        for lift in self.lifts(step.count) {
+            ship.move_stack_of_crates(&Step {
+                count: lift,
+                ..*step
+            })?;
+        }
+        Ok(())
+    }
+
+    fn lifts(&self, n: usize) -> Vec<usize> {
+        let mut lifts = vec![self.capacity; n / self.capacity];
+        let rest = n % self.capacity;
+        if rest > 0 {
+            lifts.push(rest);
+        }
+        lifts
+    }
+}
+
+/// A crane that moves multiple crates at once, but slides them under the destination stack.
+pub struct BottomLoadingCrane;
+
+impl Crane for BottomLoadingCrane {
+    fn name(&self) -> String {
+        "bottom".to_string()
+    }
+
+    fn move_crates(&self, ship: &mut Ship, step: &Step) -> Result<(), ProcedureError> {
+        ship.move_stack_of_crates_to_bottom(step)
+    }
+
+    fn loads_from_bottom(&self) -> bool {
+        true
+    }
+}
+
+/// Selects a crane model by name: `9000`, `9001`, `bottom` or `capped-<capacity>`.
+pub fn from_name(name: &str) -> Option<Box<dyn Crane>> {
+    match name {
+        "9000" => Some(Box::new(CrateMover9000)),
+        "9001" => Some(Box::new(CrateMover9001)),
+        "bottom" => Some(Box::new(BottomLoadingCrane)),
+        _ => {
+            let capacity = name.strip_prefix("capped-")?.parse().ok()?;
+            if capacity == 0 {
+                return None;
+            }
+            Some(Box::new(CappedCrane { capacity }))
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Step-by-step history of a rearrangement procedure, allowing to move back and forth between steps.
+
+use crate::Ship;
+use crate::crane::Crane;
+use crate::procedure::{ProcedureError, Step};
+
+/// Records a snapshot of the ship after every step applied by a crane.
+pub struct History<'a> {
+    crane: &'a dyn Crane,
+    steps: Vec<Step>,
+    /// `snapshots[n]` holds the ship after the first `n` steps, `snapshots[0]` is the starting ship.
+    snapshots: Vec<Ship>,
+    position: usize,
+}
+
+impl<'a> History<'a> {
+    pub fn new(ship: Ship, crane: &'a dyn Crane) -> Self {
+        Self {
+            crane,
+            steps: Vec::new(),
+            snapshots: vec![ship],
+            position: 0,
+        }
+    }
+
+    /// Records the history of a whole procedure, ending at the last step.
+    pub fn record(
+        ship: Ship,
+        crane: &'a dyn Crane,
+        steps: &[Step],
+    ) -> Result<Self, ProcedureError> {
+        let mut history = Self::new(ship, crane);
+        for step in steps {
+            history.apply(*step)?;
+        }
+        Ok(history)
+    }
+
+    /// Applies a step to the current ship. Steps that were undone before can no longer be redone.
+    pub fn apply(&mut self, step: Step) -> Result<(), ProcedureError> {
+        let mut ship = self.current().clone();
+        self.crane.move_crates(&mut ship, &step)?;
+        self.steps.truncate(self.position);
+        self.snapshots.truncate(self.position + 1);
+        self.steps.push(step);
+        self.snapshots.push(ship);
+        self.position += 1;
+        Ok(())
+    }
+
+    /// Steps back to the previous ship. Returns false if already at the start.
+    pub fn undo(&mut self) -> bool {
+        self.position > 0 && self.jump_to(self.position - 1)
+    }
+
+    /// Steps forward to a previously undone step. Returns false if there is none.
+    pub fn redo(&mut self) -> bool {
+        self.jump_to(self.position + 1)
+    }
+
+    /// Moves to the ship after the first `n` recorded steps. Returns false if `n` was never recorded.
+    pub fn jump_to(&mut self, n: usize) -> bool {
+        if n < self.snapshots.len() {
+            self.position = n;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Number of steps applied to reach the current ship.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The ship at the current position.
+    pub fn current(&self) -> &Ship {
+        &self.snapshots[self.position]
+    }
+
+    /// The step that led to the current ship, if any.
+    pub fn last_step(&self) -> Option<&Step> {
+        self.position.checked_sub(1).map(|idx| &self.steps[idx])
+    }
+
+    /// Returns the first number of steps after which both histories show a different message.
+    pub fn first_divergence(&self, other: &History) -> Option<usize> {
+        self.snapshots
+            .iter()
+            .zip(&other.snapshots)
+            .position(|(ours, theirs)| ours.get_message() != theirs.get_message())
+    }
+}
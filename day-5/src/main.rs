@@ -1,28 +1,45 @@
 // Advent of Code 2022 - Day 5
+mod animation;
+mod crane;
+mod history;
+mod procedure;
+mod solver;
+
+use std::fmt;
+
+use animation::Animation;
+use crane::{Crane, CrateMover9000, CrateMover9001};
+use history::History;
+use procedure::{ProcedureError, Step};
 
 const INPUT: &str = include_str!("./input.txt");
 
 /// Represents a ship that contains a certain number of crate stacks.
-/// Each create is represented by the char given in the input.
-#[derive(Debug)]
+/// Each create is represented by the label given in the input, which may span multiple characters.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Ship {
-    stacks: Vec<Vec<char>>,
+    stacks: Vec<Vec<String>>,
 }
 
 impl Ship {
     fn init(starting_stacks: &str) -> Self {
+        let mut lines = starting_stacks.lines().rev();
+        // Locate the stack columns from the stack numbers below the drawing.
+        let columns = find_stack_columns(
+            lines
+                .next()
+                .expect("Starting stack should contain multiple lines."),
+        );
         // Create empty ship with appropiate number of stacks.
         let mut ship = Ship {
-            stacks: vec![vec![]; get_number_of_stacks(starting_stacks)],
+            stacks: vec![vec![]; columns.len()],
         };
-        // Fill ship according to starting stacks
-        starting_stacks.lines().rev().skip(1).for_each(|l| {
-            for it in l.chars().skip(1).step_by(4).enumerate() {
-                if it.1.is_alphabetic() {
-                    ship.push(it.0, it.1);
-                }
+        // Fill ship according to starting stacks, matching each crate with the column below it.
+        for l in lines {
+            for (span, label) in find_crates(l) {
+                ship.push(closest_column(&columns, span), label);
             }
-        });
+        }
         ship
     }
 
@@ -30,98 +47,646 @@ impl Ship {
     fn get_message(&self) -> String {
         self.stacks
             .iter()
-            .filter_map(|stack| stack.last())
+            .filter_map(|stack| stack.last().map(String::as_str))
             .collect::<String>()
     }
 
+    /// Returns the numbers (1-based) of the stacks that differ between both ships.
+    fn differing_stacks(&self, other: &Ship) -> Vec<usize> {
+        (0..self.stacks.len().max(other.stacks.len()))
+            .filter(|&idx| self.stacks.get(idx) != other.stacks.get(idx))
+            .map(|idx| idx + 1)
+            .collect()
+    }
+
     /// Pushes a crate on top of the stack identified by idx.
-    fn push(&mut self, idx: usize, ch: char) {
-        self.stacks[idx].push(ch);
+    fn push(&mut self, idx: usize, label: String) {
+        self.stacks[idx].push(label);
     }
 
-    /// Removes a crate from the top of the stack identified by idx.
-    fn pop(&mut self, idx: usize) -> char {
-        self.stacks[idx]
-            .pop()
-            .expect("Stack should contain at least one item.")
+    /// Takes the top `step.count` crates off the source stack, after checking that the step can be
+    /// carried out. The crates keep their order, from bottom to top.
+    fn take_crates(&mut self, step: &Step) -> Result<Vec<String>, ProcedureError> {
+        procedure::check(self, step)?;
+        let source = &mut self.stacks[step.from - 1];
+        Ok(source.split_off(source.len() - step.count))
     }
 
-    /// Moves multiple crates one by one from one stack to another.
-    /// Note: given idices are adjusted to 0-based indexing.
-    fn move_crates(&mut self, n: usize, from_idx: usize, to_idx: usize) {
-        for _ in 0..n {
-            let moving_crate = self.pop(from_idx - 1);
-            self.push(to_idx - 1, moving_crate);
-        }
+    /// Moves multiple crates one by one from one stack to another, which reverses their order.
+    /// Note: stack numbers in the step are 1-based.
+    fn move_crates(&mut self, step: &Step) -> Result<(), ProcedureError> {
+        let moving_crates = self.take_crates(step)?;
+        self.stacks[step.to - 1].extend(moving_crates.into_iter().rev());
+        Ok(())
     }
 
     /// Moves multiple crates as a stack in one operation.
-    /// Note: given indices are adjusted to 0-based indexing.
-    fn move_stack_of_crates(&mut self, n: usize, from_idx: usize, to_idx: usize) {
-        let stack_crates = &mut self.stacks[from_idx - 1];
-        let mut moving_stack = stack_crates
-            .drain(stack_crates.len() - n..)
-            .collect::<Vec<char>>();
-        self.stacks[to_idx - 1].append(&mut moving_stack);
+    /// Note: stack numbers in the step are 1-based.
+    fn move_stack_of_crates(&mut self, step: &Step) -> Result<(), ProcedureError> {
+        let mut moving_stack = self.take_crates(step)?;
+        self.stacks[step.to - 1].append(&mut moving_stack);
+        Ok(())
+    }
+
+    /// Moves multiple crates as a stack in one operation, placing them under the destination stack.
+    /// Note: stack numbers in the step are 1-based.
+    fn move_stack_of_crates_to_bottom(&mut self, step: &Step) -> Result<(), ProcedureError> {
+        let moving_stack = self.take_crates(step)?;
+        self.stacks[step.to - 1].splice(0..0, moving_stack);
+        Ok(())
+    }
+
+    /// Width of each column, wide enough for its widest crate and its stack number, including brackets.
+    fn column_widths(&self) -> Vec<usize> {
+        self.stacks
+            .iter()
+            .enumerate()
+            .map(|(idx, stack)| {
+                let label_width = stack.iter().map(|l| l.chars().count()).max().unwrap_or(1);
+                label_width.max((idx + 1).to_string().len()) + 2
+            })
+            .collect()
+    }
+
+    /// Draws `height` rows of crates (top row first) followed by the stack numbers,
+    /// using the given column widths.
+    fn draw(&self, widths: &[usize], height: usize) -> Vec<String> {
+        let mut lines: Vec<String> = (0..height)
+            .rev()
+            .map(|level| {
+                let row: Vec<String> = self
+                    .stacks
+                    .iter()
+                    .zip(widths)
+                    .map(|(stack, &width)| match stack.get(level) {
+                        Some(label) => format!("{:<width$}", format!("[{label}]")),
+                        None => " ".repeat(width),
+                    })
+                    .collect();
+                row.join(" ")
+            })
+            .collect();
+        let numbers: Vec<String> = widths
+            .iter()
+            .enumerate()
+            .map(|(idx, &width)| format!("{:^width$}", idx + 1))
+            .collect();
+        lines.push(numbers.join(" "));
+        lines
     }
 }
 
-fn get_number_of_stacks(starting_stacks: &str) -> usize {
-    starting_stacks
-        .lines()
-        .last()
-        .expect("Starting stack should contain multiple lines.")
-        .split_whitespace()
-        .last()
-        .and_then(|s| s.parse::<usize>().ok())
-        .expect("Last number in starting stack should be parsable.")
+impl fmt::Display for Ship {
+    /// Draws the ship in the same layout as the puzzle input, so the output can be parsed by `Ship::init`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let height = self.stacks.iter().map(Vec::len).max().unwrap_or(0);
+        write!(f, "{}", self.draw(&self.column_widths(), height).join("\n"))
+    }
 }
 
-fn parse_rearrangement_procedure(procedure: &str) -> Vec<(usize, usize, usize)> {
-    procedure
-        .lines()
-        .map(|l| {
-            let mut iter = l.split_whitespace().filter_map(|s| s.parse().ok());
-            let from = iter
-                .next()
-                .expect("Should contain the numbers of crates to move.");
-            let from_position = iter.next().expect("Should contain the 'from' crate index.");
-            let to = iter.next().expect("Should contain the 'to' crate index.");
-            (from, from_position, to)
-        })
-        .collect()
+/// Returns the span (first and last char position) of every stack number in the last line of the drawing.
+fn find_stack_columns(number_line: &str) -> Vec<(usize, usize)> {
+    let mut columns = Vec::new();
+    let mut start = None;
+    for (pos, ch) in number_line.chars().chain(std::iter::once(' ')).enumerate() {
+        match (ch.is_whitespace(), start) {
+            (false, None) => start = Some(pos),
+            (true, Some(first)) => {
+                columns.push((first, pos - 1));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    columns
 }
 
-fn solve_part_one(starting_stacks: &str, procedure: &str) -> String {
-    // Fill ship according to starting_stacks
-    let mut ship = Ship::init(starting_stacks);
-    // Parse procedure and apply the steps moving a single crate at the time.
-    let procedure = parse_rearrangement_procedure(procedure);
-    for proc in procedure {
-        ship.move_crates(proc.0, proc.1, proc.2);
+/// Returns the span (position of both brackets) and label of every crate in a line of the drawing.
+fn find_crates(line: &str) -> Vec<((usize, usize), String)> {
+    let mut crates = Vec::new();
+    let mut open = None;
+    let mut label = String::new();
+    for (pos, ch) in line.chars().enumerate() {
+        match (ch, open) {
+            ('[', None) => {
+                open = Some(pos);
+                label.clear();
+            }
+            (']', Some(first)) => {
+                crates.push(((first, pos), label.clone()));
+                open = None;
+            }
+            (ch, Some(_)) => label.push(ch),
+            _ => {}
+        }
     }
-    // Find the crates that ended at the top of each stack.
-    ship.get_message()
+    crates
 }
 
-fn solve_part_two(starting_stacks: &str, procedure: &str) -> String {
+/// Returns the index of the column overlapping the crate span, or else the column closest to it.
+fn closest_column(columns: &[(usize, usize)], span: (usize, usize)) -> usize {
+    let distance = |&(start, end): &(usize, usize)| {
+        if span.0 <= end && span.1 >= start {
+            0
+        } else {
+            (start + end).abs_diff(span.0 + span.1)
+        }
+    };
+    columns
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, column)| distance(column))
+        .map(|(idx, _)| idx)
+        .expect("Starting stack should contain at least one stack number.")
+}
+
+/// Applies every step of the procedure to the ship using the given crane.
+/// The procedure is validated first, so the ship is left untouched when any step is invalid.
+fn run_procedure(ship: &mut Ship, crane: &dyn Crane, steps: &[Step]) -> Result<(), ProcedureError> {
+    procedure::dry_run(ship, steps)?;
+    for step in steps {
+        crane.move_crates(ship, step)?;
+    }
+    Ok(())
+}
+
+fn solve(
+    starting_stacks: &str,
+    procedure: &str,
+    crane: &dyn Crane,
+) -> Result<String, ProcedureError> {
     // Fill ship according to starting_stacks
     let mut ship = Ship::init(starting_stacks);
-    // Parse procedure and apply the steps moving stacks of crates at the time.
-    let procedure = parse_rearrangement_procedure(procedure);
-    for proc in procedure {
-        ship.move_stack_of_crates(proc.0, proc.1, proc.2);
-    }
+    // Parse procedure and let the crane apply the steps.
+    let steps = procedure::parse_rearrangement_procedure(procedure)?;
+    run_procedure(&mut ship, crane, &steps)?;
     // Find the crates that ended at the top of each stack.
-    ship.get_message()
+    Ok(ship.get_message())
+}
+
+fn solve_part_one(starting_stacks: &str, procedure: &str) -> Result<String, ProcedureError> {
+    // The CrateMover 9000 moves a single crate at the time.
+    solve(starting_stacks, procedure, &CrateMover9000)
+}
+
+fn solve_part_two(starting_stacks: &str, procedure: &str) -> Result<String, ProcedureError> {
+    // The CrateMover 9001 moves stacks of crates at the time.
+    solve(starting_stacks, procedure, &CrateMover9001)
+}
+
+/// Selects the crane model given as command line argument at `position`, defaulting to the CrateMover 9000.
+fn crane_argument(position: usize) -> Box<dyn Crane> {
+    std::env::args()
+        .nth(position)
+        .map(|name| {
+            crane::from_name(&name).unwrap_or_else(|| panic!("Unknown crane model {name:?}"))
+        })
+        .unwrap_or_else(|| Box::new(CrateMover9000))
 }
 
 fn main() {
     let (starting_stacks, procedure) = INPUT
         .split_once("\n\n")
         .unwrap_or_else(|| panic!("Error while parsing input!"));
-    let answer_part_one = solve_part_one(starting_stacks, procedure);
-    println!("Part one: the top crates create the message: {answer_part_one}");
-    let answer_part_two = solve_part_two(starting_stacks, procedure);
-    println!("Part two: the top crates create the message: {answer_part_two}");
+    let steps = match procedure::parse_rearrangement_procedure(procedure) {
+        Ok(steps) => steps,
+        Err(err) => {
+            println!("Invalid procedure: {err}");
+            return;
+        }
+    };
+    match std::env::args().nth(1).as_deref() {
+        // Only check whether the procedure can be carried out, without moving any crates.
+        Some("check") => {
+            let ship = Ship::init(starting_stacks);
+            match procedure::dry_run(&ship, &steps) {
+                Ok(()) => println!("All {} steps of the procedure are valid.", steps.len()),
+                Err(err) => println!("Invalid procedure: {err}"),
+            }
+        }
+        // Step interactively through the procedure: `n` (next), `p` (previous), a step number, or `q`.
+        Some("step") => {
+            let crane = crane_argument(2);
+            let mut history =
+                match History::record(Ship::init(starting_stacks), crane.as_ref(), &steps) {
+                    Ok(history) => history,
+                    Err(err) => {
+                        println!("Invalid procedure: {err}");
+                        return;
+                    }
+                };
+            history.jump_to(0);
+            println!("{}", history.current());
+            for command in std::io::stdin().lines().map_while(Result::ok) {
+                let moved = match command.trim() {
+                    "n" => history.redo(),
+                    "p" => history.undo(),
+                    "q" => break,
+                    n => n.parse().is_ok_and(|n| history.jump_to(n)),
+                };
+                if !moved {
+                    println!(
+                        "Cannot move there, the procedure has {} steps.",
+                        steps.len()
+                    );
+                    continue;
+                }
+                match history.last_step() {
+                    Some(step) => println!("After step {} ({step}):", history.position()),
+                    None => println!("Starting stacks:"),
+                }
+                println!("{}", history.current());
+            }
+        }
+        // Animate the procedure on the terminal, optionally choosing a crane model and frames per second.
+        Some("animate") => {
+            let crane = crane_argument(2);
+            let fps = std::env::args()
+                .nth(3)
+                .map(|fps| fps.parse().expect("Frames per second should be a number"))
+                .unwrap_or(30);
+            let ship = Ship::init(starting_stacks);
+            let animation = Animation::new(&ship, crane.as_ref(), fps);
+            if let Err(err) = animation.run(ship, &steps, &mut std::io::stdout()) {
+                println!("Animation stopped: {err}");
+            }
+        }
+        // Find a short procedure from the starting stacks to the drawing in the given file.
+        Some("reverse") => {
+            let path = std::env::args()
+                .nth(2)
+                .expect("Should be given a file with the target drawing");
+            let target = std::fs::read_to_string(&path)
+                .unwrap_or_else(|err| panic!("Could not read {path}: {err}"));
+            let crane = crane_argument(3);
+            let start = Ship::init(starting_stacks);
+            let target = Ship::init(target.trim_end_matches('\n'));
+            match solver::find_procedure(&start, &target, crane.as_ref(), 1_000_000) {
+                Ok(steps) => steps.iter().for_each(|step| println!("{step}")),
+                Err(err) => println!("No procedure for crane {}: {err}", crane.name()),
+            }
+        }
+        // Find the first step after which the CrateMover 9000 and 9001 show a different message.
+        Some("diverge") => {
+            let ship = Ship::init(starting_stacks);
+            let histories = History::record(ship.clone(), &CrateMover9000, &steps)
+                .and_then(|old| Ok((old, History::record(ship, &CrateMover9001, &steps)?)));
+            match histories {
+                Ok((mut old, mut new)) => match old.first_divergence(&new) {
+                    Some(n) => {
+                        old.jump_to(n);
+                        new.jump_to(n);
+                        let step = old
+                            .last_step()
+                            .expect("Ships should not diverge at the start");
+                        println!(
+                            "Messages diverge after step {n} (line {}), stacks {:?} differ.",
+                            step.line,
+                            old.current().differing_stacks(new.current())
+                        );
+                        println!("CrateMover 9000:\n{}\n", old.current());
+                        println!("CrateMover 9001:\n{}", new.current());
+                    }
+                    None => println!("Messages never diverge."),
+                },
+                Err(err) => println!("Invalid procedure: {err}"),
+            }
+        }
+        // Run the procedure with another crane model, e.g. `capped-3` or `bottom`.
+        Some(_) => {
+            let crane = crane_argument(1);
+            match solve(starting_stacks, procedure, crane.as_ref()) {
+                Ok(answer) => println!(
+                    "Crane {}: the top crates create the message: {answer}",
+                    crane.name()
+                ),
+                Err(err) => println!("Crane {}: invalid procedure: {err}", crane.name()),
+            }
+        }
+        None => {
+            match solve_part_one(starting_stacks, procedure) {
+                Ok(answer) => println!("Part one: the top crates create the message: {answer}"),
+                Err(err) => println!("Part one: invalid procedure: {err}"),
+            }
+            match solve_part_two(starting_stacks, procedure) {
+                Ok(answer) => println!("Part two: the top crates create the message: {answer}"),
+                Err(err) => println!("Part two: invalid procedure: {err}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::animation::Animation;
+    use crate::crane::{self, Crane};
+    use crate::history::History;
+    use crate::procedure::{self, ParseError, ProcedureError, Step};
+    use crate::solver::{self, SolveError};
+
+    const EXAMPLE_STACKS: &str = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 ";
+
+    const EXAMPLE_PROCEDURE: &str = "move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2";
+
+    #[test]
+    fn part1() {
+        let answer = crate::solve_part_one(EXAMPLE_STACKS, EXAMPLE_PROCEDURE);
+        assert_eq!(answer.unwrap(), "CMZ");
+    }
+
+    #[test]
+    fn part2() {
+        let answer = crate::solve_part_two(EXAMPLE_STACKS, EXAMPLE_PROCEDURE);
+        assert_eq!(answer.unwrap(), "MCD");
+    }
+
+    #[test]
+    fn capped_crane() {
+        let single = crane::from_name("capped-1").unwrap();
+        let answer = crate::solve(EXAMPLE_STACKS, EXAMPLE_PROCEDURE, single.as_ref());
+        assert_eq!(answer.unwrap(), "CMZ");
+        let double = crane::from_name("capped-2").unwrap();
+        let answer = crate::solve(EXAMPLE_STACKS, EXAMPLE_PROCEDURE, double.as_ref());
+        assert_eq!(answer.unwrap(), "MCZ");
+        assert!(crane::from_name("capped-0").is_none());
+    }
+
+    #[test]
+    fn invalid_procedures() {
+        let ship = crate::Ship::init(EXAMPLE_STACKS);
+        let steps =
+            procedure::parse_rearrangement_procedure("move 1 from 2 to 1\nmove 4 from 1 to 3")
+                .unwrap();
+        assert_eq!(
+            procedure::dry_run(&ship, &steps),
+            Err(ProcedureError::NotEnoughCrates {
+                line: 2,
+                stack: 1,
+                requested: 4,
+                available: 3
+            })
+        );
+        let steps = procedure::parse_rearrangement_procedure("move 1 from 0 to 1").unwrap();
+        assert_eq!(
+            procedure::dry_run(&ship, &steps),
+            Err(ProcedureError::UnknownStack {
+                line: 1,
+                stack: 0,
+                stacks: 3
+            })
+        );
+        let answer = crate::solve(EXAMPLE_STACKS, "move 1 from 2 to 4", &crane::CrateMover9001);
+        assert!(matches!(
+            answer,
+            Err(ProcedureError::UnknownStack { stack: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn cranes_reject_invalid_steps() {
+        let ship = crate::Ship::init(EXAMPLE_STACKS);
+        let invalid = [
+            // Stack 3 only holds a single crate.
+            (2, 3, 1),
+            (1, 0, 1),
+            (1, 1, 0),
+            (1, 1, 4),
+        ];
+        for name in ["9000", "9001", "bottom", "capped-1"] {
+            let crane = crane::from_name(name).unwrap();
+            for (count, from, to) in invalid {
+                let step = Step {
+                    count,
+                    from,
+                    to,
+                    line: 7,
+                };
+                let mut moved = ship.clone();
+                let err = crane.move_crates(&mut moved, &step).unwrap_err();
+                assert_eq!(moved, ship, "{name}: {step}");
+                assert!(
+                    matches!(
+                        err,
+                        ProcedureError::NotEnoughCrates { line: 7, .. }
+                            | ProcedureError::UnknownStack { line: 7, .. }
+                    ),
+                    "{name}: {step}"
+                );
+            }
+        }
+        // Moving from an empty stack fails, rather than panicking.
+        let mut empty = crate::Ship::init("    [A]\n 1   2 ");
+        let step = Step {
+            count: 1,
+            from: 1,
+            to: 2,
+            line: 1,
+        };
+        assert!(
+            crane::CrateMover9000
+                .move_crates(&mut empty, &step)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn history() {
+        let ship = crate::Ship::init(EXAMPLE_STACKS);
+        let steps = procedure::parse_rearrangement_procedure(EXAMPLE_PROCEDURE).unwrap();
+        let mut history = History::record(ship.clone(), &crane::CrateMover9000, &steps).unwrap();
+        assert_eq!(history.position(), 4);
+        assert_eq!(history.current().get_message(), "CMZ");
+        assert!(history.undo());
+        assert_eq!(history.current().get_message(), "MZ");
+        assert!(history.redo());
+        assert!(!history.redo());
+        assert!(history.jump_to(0));
+        assert_eq!(history.current(), &ship);
+        assert!(!history.undo());
+        // Applying a step after undoing discards the undone steps.
+        history.apply(steps[0]).unwrap();
+        assert!(!history.redo());
+        assert!(!history.jump_to(2));
+    }
+
+    #[test]
+    fn history_divergence() {
+        let ship = crate::Ship::init(EXAMPLE_STACKS);
+        let steps = procedure::parse_rearrangement_procedure(EXAMPLE_PROCEDURE).unwrap();
+        let mut old = History::record(ship.clone(), &crane::CrateMover9000, &steps).unwrap();
+        let mut new = History::record(ship, &crane::CrateMover9001, &steps).unwrap();
+        assert_eq!(old.first_divergence(&new), Some(2));
+        assert!(old.jump_to(2) && new.jump_to(2));
+        assert_eq!(old.current().differing_stacks(new.current()), vec![3]);
+    }
+
+    #[test]
+    fn animation() {
+        // Whole stacks moving onto a stack, and crates moving within a single stack.
+        let tall = "[C] [F]\n[B] [E]\n[A] [D]\n 1   2 ";
+        let cases = [
+            (EXAMPLE_STACKS, EXAMPLE_PROCEDURE),
+            (tall, "move 3 from 1 to 2\nmove 1 from 2 to 2"),
+        ];
+        for (stacks, procedure) in cases {
+            let steps = procedure::parse_rearrangement_procedure(procedure).unwrap();
+            for name in ["9000", "9001", "capped-2", "bottom"] {
+                let crane = crane::from_name(name).unwrap();
+                let ship = crate::Ship::init(stacks);
+                let labels: Vec<String> = ship.stacks.iter().flatten().cloned().collect();
+                let mut expected = ship.clone();
+                crate::run_procedure(&mut expected, crane.as_ref(), &steps).unwrap();
+                let animation = Animation::new(&ship, crane.as_ref(), 0);
+                let mut out = Vec::new();
+                let ship = animation.run(ship, &steps, &mut out).unwrap();
+                assert_eq!(ship, expected);
+                let out = String::from_utf8(out).unwrap();
+                assert!(out.ends_with(&format!(
+                    "The top crates create the message: {}\n",
+                    expected.get_message()
+                )));
+                let frames: Vec<&str> = out.split("\x1b[H\x1b[2J").skip(1).collect();
+                // Crates never cover each other.
+                for frame in &frames {
+                    for label in &labels {
+                        assert!(frame.contains(&format!("[{label}]")), "{name}:\n{frame}");
+                    }
+                }
+                // The last crates dropped by a step end up where the ship shows them after the step.
+                for number in 1..=steps.len() {
+                    let status = format!("\n\nStep {number}/");
+                    let step: Vec<&&str> = frames.iter().filter(|f| f.contains(&status)).collect();
+                    assert_eq!(step[step.len() - 2], step[step.len() - 1], "{name}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reverse_procedure() {
+        let start = crate::Ship::init(EXAMPLE_STACKS);
+        let steps = procedure::parse_rearrangement_procedure(EXAMPLE_PROCEDURE).unwrap();
+        let mut target = start.clone();
+        crate::run_procedure(&mut target, &crane::CrateMover9001, &steps).unwrap();
+        let found =
+            solver::find_procedure(&start, &target, &crane::CrateMover9001, 100_000).unwrap();
+        assert!(found.len() <= steps.len());
+        let mut ship = start.clone();
+        crate::run_procedure(&mut ship, &crane::CrateMover9001, &found).unwrap();
+        assert_eq!(ship, target);
+        assert_eq!(
+            solver::find_procedure(&start, &start, &crane::CrateMover9000, 1),
+            Ok(vec![])
+        );
+        assert_eq!(
+            solver::find_procedure(&start, &target, &crane::CrateMover9001, 2),
+            Err(SolveError::LimitReached { states: 2 })
+        );
+    }
+
+    #[test]
+    fn reverse_procedure_impossible() {
+        let start = crate::Ship::init("[B]    \n[A]    \n 1   2 ");
+        let swapped = crate::Ship::init("[A]    \n[B]    \n 1   2 ");
+        // A CrateMover 9000 with two stacks can never change the order of the crates.
+        assert_eq!(
+            solver::find_procedure(&start, &swapped, &crane::CrateMover9000, 100),
+            Err(SolveError::Unreachable { states: 3 })
+        );
+        assert!(solver::find_procedure(&start, &swapped, &crane::CrateMover9001, 100).is_ok());
+        let other = crate::Ship::init("[C]    \n[A]    \n 1   2 ");
+        assert_eq!(
+            solver::find_procedure(&start, &other, &crane::CrateMover9001, 100),
+            Err(SolveError::DifferentCrates)
+        );
+    }
+
+    #[test]
+    fn procedure_grammar() {
+        let procedure = "# Rearrangement procedure
+
+move 1 from 2 to 1 # a single crate
+  move  12 from 10 to 3
+";
+        let steps = procedure::parse_rearrangement_procedure(procedure).unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                Step {
+                    count: 1,
+                    from: 2,
+                    to: 1,
+                    line: 3
+                },
+                Step {
+                    count: 12,
+                    from: 10,
+                    to: 3,
+                    line: 4
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn procedure_grammar_errors() {
+        let error = |procedure| procedure::parse_rearrangement_procedure(procedure).unwrap_err();
+        assert_eq!(
+            error("move 1 from 2 to 1\nmove 3 to 1 from 2"),
+            ParseError {
+                line: 2,
+                column: 8,
+                found: Some("to".to_string()),
+                expected: "from"
+            }
+        );
+        assert_eq!(
+            error("move -1 from 2 to 1").to_string(),
+            "line 1, column 6: expected a number, found `-1`"
+        );
+        assert_eq!(
+            error("move 1 from 2").to_string(),
+            "line 1: expected to, found the end of the line"
+        );
+        assert_eq!(error("move 1 from 2 to 1 now").column, 20);
+        assert_eq!(error("Move 1 from 2 to 1").expected, "move");
+        assert!(matches!(
+            crate::solve_part_one(EXAMPLE_STACKS, "move one from 2 to 1"),
+            Err(ProcedureError::Syntax(_))
+        ));
+    }
+
+    #[test]
+    fn render_round_trip() {
+        let ship = crate::Ship::init(EXAMPLE_STACKS);
+        assert_eq!(ship.to_string(), EXAMPLE_STACKS);
+        assert_eq!(crate::Ship::init(&ship.to_string()), ship);
+    }
+
+    #[test]
+    fn multi_character_labels_and_many_stacks() {
+        let drawing = "[AB]                                         [XYZ]
+[C]  [D]  [E]  [F]  [G]  [H]  [I]  [J]  [K]  [L]  [M]  
+ 1    2    3    4    5    6    7    8    9    10   11   ";
+        let ship = crate::Ship::init(drawing);
+        assert_eq!(ship.stacks.len(), 11);
+        assert_eq!(ship.stacks[0], vec!["C", "AB"]);
+        assert_eq!(ship.stacks[9], vec!["L", "XYZ"]);
+        assert_eq!(ship.stacks[10], vec!["M"]);
+        assert_eq!(crate::Ship::init(&ship.to_string()), ship);
+        assert_eq!(ship.get_message(), "ABDEFGHIJKXYZM");
+    }
+
+    #[test]
+    fn bottom_loading_crane() {
+        let bottom = crane::from_name("bottom").unwrap();
+        let answer = crate::solve(EXAMPLE_STACKS, EXAMPLE_PROCEDURE, bottom.as_ref());
+        assert_eq!(answer.unwrap(), "MCP");
+    }
 }
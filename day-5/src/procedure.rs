@@ -0,0 +1,237 @@
+//! Steps of the rearrangement procedure and their validation against a `Ship`.
+
+use std::error::Error;
+use std::fmt;
+use std::iter::Peekable;
+
+use crate::Ship;
+
+/// A single `move <count> from <from> to <to>` step of the rearrangement procedure.
+/// Stack indices are 1-based, as in the puzzle input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub count: usize,
+    pub from: usize,
+    pub to: usize,
+    /// Line number (1-based) of the step within the procedure.
+    pub line: usize,
+}
+
+impl fmt::Display for Step {
+    /// Writes the step as a line of the rearrangement procedure.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from, self.to)
+    }
+}
+
+/// A line of the procedure that does not follow the `move <count> from <from> to <to>` grammar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    /// Column (1-based) of the offending token, 0 if the line ended too early.
+    pub column: usize,
+    /// The offending token, `None` if the line ended too early.
+    pub found: Option<String>,
+    pub expected: &'static str,
+}
+
+impl ParseError {
+    fn unexpected(line: usize, next: Option<(usize, &str)>, expected: &'static str) -> Self {
+        Self {
+            line,
+            column: next.map_or(0, |(column, _)| column),
+            found: next.map(|(_, token)| token.to_string()),
+            expected,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.found {
+            Some(token) => write!(
+                f,
+                "line {}, column {}: expected {}, found `{token}`",
+                self.line, self.column, self.expected
+            ),
+            None => write!(
+                f,
+                "line {}: expected {}, found the end of the line",
+                self.line, self.expected
+            ),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Reasons why a step of the procedure cannot be carried out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcedureError {
+    /// The procedure does not follow the grammar.
+    Syntax(ParseError),
+    /// The step refers to a stack that does not exist on the ship.
+    UnknownStack {
+        line: usize,
+        stack: usize,
+        stacks: usize,
+    },
+    /// The step moves more crates than its source stack holds at that point.
+    NotEnoughCrates {
+        line: usize,
+        stack: usize,
+        requested: usize,
+        available: usize,
+    },
+}
+
+impl fmt::Display for ProcedureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcedureError::Syntax(err) => err.fmt(f),
+            ProcedureError::UnknownStack {
+                line,
+                stack,
+                stacks,
+            } => write!(
+                f,
+                "line {line}: stack {stack} does not exist, stacks are numbered 1 to {stacks}"
+            ),
+            ProcedureError::NotEnoughCrates {
+                line,
+                stack,
+                requested,
+                available,
+            } => write!(
+                f,
+                "line {line}: cannot move {requested} crates from stack {stack}, which holds {available}"
+            ),
+        }
+    }
+}
+
+impl Error for ProcedureError {}
+
+impl From<ParseError> for ProcedureError {
+    fn from(err: ParseError) -> Self {
+        ProcedureError::Syntax(err)
+    }
+}
+
+/// Parses the rearrangement procedure, where every line reads `move <count> from <from> to <to>`.
+/// Blank lines are skipped and `#` starts a comment that runs until the end of the line.
+pub fn parse_rearrangement_procedure(procedure: &str) -> Result<Vec<Step>, ParseError> {
+    let mut steps = Vec::new();
+    for (idx, l) in procedure.lines().enumerate() {
+        let line = idx + 1;
+        let code = l.split_once('#').map_or(l, |(code, _)| code);
+        let mut tokens = tokenize(code);
+        if tokens.peek().is_none() {
+            continue;
+        }
+        let mut number = |keyword: &'static str| -> Result<usize, ParseError> {
+            expect_keyword(&mut tokens, line, keyword)?;
+            expect_number(&mut tokens, line)
+        };
+        let count = number("move")?;
+        let from = number("from")?;
+        let to = number("to")?;
+        if let Some((column, token)) = tokens.next() {
+            return Err(ParseError {
+                line,
+                column,
+                found: Some(token.to_string()),
+                expected: "end of line",
+            });
+        }
+        steps.push(Step {
+            count,
+            from,
+            to,
+            line,
+        });
+    }
+    Ok(steps)
+}
+
+/// Splits a line into whitespace separated tokens, together with their 1-based column.
+fn tokenize(line: &str) -> Peekable<std::vec::IntoIter<(usize, &str)>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (column, (pos, ch)) in line.char_indices().chain([(line.len(), ' ')]).enumerate() {
+        match (ch.is_whitespace(), start) {
+            (false, None) => start = Some((column + 1, pos)),
+            (true, Some((first_column, first_pos))) => {
+                tokens.push((first_column, &line[first_pos..pos]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens.into_iter().peekable()
+}
+
+/// Consumes the next token, which should be the given keyword.
+fn expect_keyword<'a>(
+    tokens: &mut impl Iterator<Item = (usize, &'a str)>,
+    line: usize,
+    keyword: &'static str,
+) -> Result<(), ParseError> {
+    match tokens.next() {
+        Some((_, token)) if token == keyword => Ok(()),
+        next => Err(ParseError::unexpected(line, next, keyword)),
+    }
+}
+
+/// Consumes the next token, which should be a non-negative number.
+fn expect_number<'a>(
+    tokens: &mut impl Iterator<Item = (usize, &'a str)>,
+    line: usize,
+) -> Result<usize, ParseError> {
+    let next = tokens.next();
+    next.filter(|(_, token)| token.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|(_, token)| token.parse().ok())
+        .ok_or_else(|| ParseError::unexpected(line, next, "a number"))
+}
+
+/// Checks a single step against the current height of each stack.
+fn check_step(heights: &[usize], step: &Step) -> Result<(), ProcedureError> {
+    for stack in [step.from, step.to] {
+        if stack == 0 || stack > heights.len() {
+            return Err(ProcedureError::UnknownStack {
+                line: step.line,
+                stack,
+                stacks: heights.len(),
+            });
+        }
+    }
+    let available = heights[step.from - 1];
+    if step.count > available {
+        return Err(ProcedureError::NotEnoughCrates {
+            line: step.line,
+            stack: step.from,
+            requested: step.count,
+            available,
+        });
+    }
+    Ok(())
+}
+
+/// Checks whether a single step can be carried out on the ship as it is now.
+pub fn check(ship: &Ship, step: &Step) -> Result<(), ProcedureError> {
+    let heights: Vec<usize> = ship.stacks.iter().map(Vec::len).collect();
+    check_step(&heights, step)
+}
+
+/// Simulates the procedure on the stack heights of the ship without moving any crates,
+/// reporting the first step that cannot be carried out.
+/// Every crane moves the same number of crates, so the outcome holds for all crane models.
+pub fn dry_run(ship: &Ship, steps: &[Step]) -> Result<(), ProcedureError> {
+    let mut heights: Vec<usize> = ship.stacks.iter().map(Vec::len).collect();
+    for step in steps {
+        check_step(&heights, step)?;
+        heights[step.from - 1] -= step.count;
+        heights[step.to - 1] += step.count;
+    }
+    Ok(())
+}
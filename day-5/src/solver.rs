@@ -0,0 +1,112 @@
+//! Searches for a rearrangement procedure that turns one ship into another.
+
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+
+use crate::Ship;
+use crate::crane::Crane;
+use crate::procedure::Step;
+
+/// Reasons why no procedure between two ships was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// The ships have a different number of stacks.
+    DifferentStacks,
+    /// The ships do not carry the same crates, so no crane can turn one into the other.
+    DifferentCrates,
+    /// Every arrangement reachable by the crane was visited without finding the target.
+    Unreachable { states: usize },
+    /// The search was stopped after visiting the maximum number of arrangements.
+    LimitReached { states: usize },
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::DifferentStacks => write!(f, "the ships have a different number of stacks"),
+            SolveError::DifferentCrates => write!(f, "the ships do not carry the same crates"),
+            SolveError::Unreachable { states } => write!(
+                f,
+                "the target cannot be reached, all {states} reachable arrangements were visited"
+            ),
+            SolveError::LimitReached { states } => {
+                write!(f, "no procedure found after visiting {states} arrangements")
+            }
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+/// Finds a procedure with the fewest steps that lets the crane turn `start` into `target`,
+/// using a breadth-first search over the arrangements of the crates visiting at most `max_states` of them.
+pub fn find_procedure(
+    start: &Ship,
+    target: &Ship,
+    crane: &dyn Crane,
+    max_states: usize,
+) -> Result<Vec<Step>, SolveError> {
+    if start.stacks.len() != target.stacks.len() {
+        return Err(SolveError::DifferentStacks);
+    }
+    if sorted_crates(start) != sorted_crates(target) {
+        return Err(SolveError::DifferentCrates);
+    }
+    // Every visited arrangement, together with the arrangement and step it was reached from.
+    let mut states = vec![(start.clone(), None)];
+    let mut seen = HashMap::from([(start.clone(), 0)]);
+    let mut queue = VecDeque::from([0]);
+    while let Some(idx) = queue.pop_front() {
+        if &states[idx].0 == target {
+            return Ok(trace_steps(&states, idx));
+        }
+        let ship = states[idx].0.clone();
+        for from in 1..=ship.stacks.len() {
+            for to in (1..=ship.stacks.len()).filter(|&to| to != from) {
+                for count in 1..=ship.stacks[from - 1].len() {
+                    let step = Step {
+                        count,
+                        from,
+                        to,
+                        line: 0,
+                    };
+                    let mut next = ship.clone();
+                    if crane.move_crates(&mut next, &step).is_err() || seen.contains_key(&next) {
+                        continue;
+                    }
+                    if states.len() == max_states {
+                        return Err(SolveError::LimitReached { states: max_states });
+                    }
+                    seen.insert(next.clone(), states.len());
+                    queue.push_back(states.len());
+                    states.push((next, Some((idx, step))));
+                }
+            }
+        }
+    }
+    Err(SolveError::Unreachable {
+        states: states.len(),
+    })
+}
+
+/// Returns the labels of all crates on the ship in sorted order.
+fn sorted_crates(ship: &Ship) -> Vec<&String> {
+    let mut crates: Vec<&String> = ship.stacks.iter().flatten().collect();
+    crates.sort();
+    crates
+}
+
+/// Follows the recorded steps back from the arrangement at idx to the start, numbering the steps as lines.
+fn trace_steps(states: &[(Ship, Option<(usize, Step)>)], mut idx: usize) -> Vec<Step> {
+    let mut steps = Vec::new();
+    while let Some((previous, step)) = states[idx].1 {
+        steps.push(step);
+        idx = previous;
+    }
+    steps.reverse();
+    for (line, step) in steps.iter_mut().enumerate() {
+        step.line = line + 1;
+    }
+    steps
+}
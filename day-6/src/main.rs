@@ -1,120 +1,561 @@
 // Advent of Code 2022 - Day 6: Tuning Trouble
 
+mod stream;
+mod tokens;
+mod unique;
+
+use std::time::Instant;
+
+use unique::Strategy;
+
 const INPUT: &str = include_str!("./input.txt");
 
-fn parse_input(input: &str) -> &[u8] {
-    input.trim().as_bytes()
+/// Returns the datastream as text, so markers can be counted in characters rather than bytes.
+fn parse_input_chars(input: &str) -> &str {
+    input.trim()
 }
 
-fn all_unique<const N: usize>(bytes: &[u8; N]) -> bool {
-    for i in 0..N {
-        for j in i + 1..N {
-            if bytes[i] == bytes[j] {
-                return false;
-            }
+/// Follows a datastream token by token, telling whether the last `len` tokens form a marker.
+trait Detector {
+    type Token;
+
+    fn new(len: usize) -> Self;
+
+    /// Adds the next token of the datastream, returning true if the last `len` tokens are all unique.
+    fn push(&mut self, token: Self::Token) -> bool;
+}
+
+/// Follows a datastream byte by byte, keeping track of the longest run of unique bytes at its end.
+/// Remembering where each byte was last seen allows the run to be shortened past a repeated byte
+/// immediately, so every byte is handled in constant time regardless of the marker length.
+struct MarkerDetector {
+    len: usize,
+    last_seen: [Option<u64>; 256],
+    /// Position of the first byte of the run of unique bytes.
+    start: u64,
+    pushed: u64,
+}
+
+impl Detector for MarkerDetector {
+    type Token = u8;
+
+    fn new(len: usize) -> Self {
+        Self {
+            len,
+            last_seen: [None; 256],
+            start: 0,
+            pushed: 0,
         }
     }
-    true
+
+    fn push(&mut self, byte: u8) -> bool {
+        if let Some(previous) = self.last_seen[byte as usize]
+            && previous >= self.start
+        {
+            self.start = previous + 1;
+        }
+        self.last_seen[byte as usize] = Some(self.pushed);
+        self.pushed += 1;
+        self.pushed - self.start >= self.len as u64
+    }
+}
+
+/// Whether consecutive markers may share tokens of the datastream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MarkerMode {
+    /// Every window of unique tokens is a marker, even when it overlaps the previous one.
+    Overlapping,
+    /// A marker only consists of tokens after the end of the previous marker.
+    NonOverlapping,
 }
 
-fn solve_part_one(input: &str) -> usize {
-    let buffer = parse_input(input);
-    for i in 0..=(buffer.len() - 4) {
-        let window: &[u8; 4] = buffer[i..i + 4].try_into().unwrap();
-        if all_unique(window) {
-            return i + 4;
+/// Iterator over the end positions, counted in tokens, of all markers of `len` unique tokens.
+struct Markers<I, D> {
+    tokens: I,
+    position: usize,
+    len: usize,
+    mode: MarkerMode,
+    detector: D,
+}
+
+impl<I: Iterator, D: Detector<Token = I::Item>> Markers<I, D> {
+    /// A marker consists of at least one token, so no markers are found for a length of 0.
+    fn new(tokens: I, len: usize, mode: MarkerMode) -> Self {
+        Markers {
+            tokens,
+            position: 0,
+            len,
+            mode,
+            detector: D::new(len),
         }
     }
-    panic!("Input should contain a `start-of-packet` marker");
 }
 
-fn solve_part_two(input: &str) -> usize {
-    let buffer = parse_input(input);
-    for i in 0..=(buffer.len() - 14) {
-        let window: &[u8; 14] = buffer[i..i + 14].try_into().unwrap();
-        if all_unique(window) {
-            return i + 14;
+impl<I: Iterator, D: Detector<Token = I::Item>> Iterator for Markers<I, D> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.len == 0 {
+            return None;
+        }
+        for token in self.tokens.by_ref() {
+            self.position += 1;
+            if self.detector.push(token) {
+                if self.mode == MarkerMode::NonOverlapping {
+                    self.detector = D::new(self.len);
+                }
+                return Some(self.position);
+            }
         }
+        None
     }
-    panic!("Input should contain a `start-of-message` marker");
+}
+
+/// Returns the number of bytes processed up to and including each window of `len` unique bytes.
+fn markers(
+    buffer: &[u8],
+    len: usize,
+    mode: MarkerMode,
+) -> Markers<std::iter::Copied<std::slice::Iter<'_, u8>>, MarkerDetector> {
+    Markers::new(buffer.iter().copied(), len, mode)
+}
+
+/// Returns the number of bytes processed up to and including the first window of `len` unique bytes.
+fn find_marker(buffer: &[u8], len: usize) -> Option<usize> {
+    markers(buffer, len, MarkerMode::Overlapping).next()
+}
+
+/// A part of the datastream, from the start of a marker up to the start of the next one.
+#[derive(Debug, PartialEq, Eq)]
+struct Segment<'a> {
+    /// Position of the first character of the marker.
+    offset: usize,
+    marker: &'a str,
+    payload: &'a str,
+}
+
+/// Splits the datastream at its non-overlapping markers of `len` characters.
+/// Characters in front of the first marker are not part of any segment.
+fn segments(datastream: &str, len: usize) -> Vec<Segment<'_>> {
+    let ends: Vec<usize> =
+        tokens::char_markers(datastream, len, MarkerMode::NonOverlapping).collect();
+    // Byte position of every character, and of the end of the datastream.
+    let bytes: Vec<usize> = datastream
+        .char_indices()
+        .map(|(idx, _)| idx)
+        .chain(std::iter::once(datastream.len()))
+        .collect();
+    ends.iter()
+        .enumerate()
+        .map(|(idx, &end)| {
+            let payload_end = ends.get(idx + 1).map_or(bytes.len() - 1, |next| next - len);
+            Segment {
+                offset: end - len,
+                marker: &datastream[bytes[end - len]..bytes[end]],
+                payload: &datastream[bytes[end]..bytes[payload_end]],
+            }
+        })
+        .collect()
+}
+
+fn solve_part_one(input: &str) -> Option<usize> {
+    tokens::find_char_marker(parse_input_chars(input), 4)
+}
+
+fn solve_part_two(input: &str) -> Option<usize> {
+    tokens::find_char_marker(parse_input_chars(input), 14)
+}
+
+/// Reads the marker length given as command line argument at `position`, defaulting to 4.
+fn marker_length_argument(position: usize) -> usize {
+    std::env::args().nth(position).map_or(4, |len| {
+        len.parse().expect("Marker length should be a number")
+    })
 }
 
 fn main() {
-    let part_one_answer = solve_part_one(INPUT);
-    println!("Part one:\n{part_one_answer}");
-    let part_two_answer = solve_part_two(INPUT);
-    println!("Part two:\n{part_two_answer}");
+    match solve_part_one(INPUT) {
+        Some(part_one_answer) => println!("Part one:\n{part_one_answer}"),
+        None => println!("Part one:\nno start-of-packet marker found"),
+    }
+    match solve_part_two(INPUT) {
+        Some(part_two_answer) => println!("Part two:\n{part_two_answer}"),
+        None => println!("Part two:\nno start-of-message marker found"),
+    }
+    match std::env::args().nth(1).as_deref() {
+        // Scan a file, or stdin when no file is given, without loading it into memory.
+        Some("stream") => {
+            let markers = match std::env::args().nth(2) {
+                Some(path) => {
+                    std::fs::File::open(path).and_then(|file| stream::scan(file, &[4, 14]))
+                }
+                None => stream::scan(std::io::stdin().lock(), &[4, 14]),
+            };
+            match markers {
+                Ok(markers) => {
+                    for marker in markers {
+                        println!(
+                            "Marker of length {} ends at character {} (byte {})",
+                            marker.len, marker.offset, marker.byte_offset
+                        );
+                    }
+                }
+                Err(err) => println!("Could not read datastream: {err}"),
+            }
+        }
+        // List all markers of a given length, `separate` leaves out markers overlapping the previous one.
+        Some("all") => {
+            let len = marker_length_argument(2);
+            let mode = match std::env::args().nth(3).as_deref() {
+                Some("separate") => MarkerMode::NonOverlapping,
+                _ => MarkerMode::Overlapping,
+            };
+            let positions: Vec<String> = tokens::char_markers(parse_input_chars(INPUT), len, mode)
+                .map(|position| position.to_string())
+                .collect();
+            println!(
+                "{} markers of length {len}:\n{}",
+                positions.len(),
+                positions.join("\n")
+            );
+        }
+        // Split the datastream into packets (or messages with length 14) at their markers.
+        Some("segments") => {
+            let len = marker_length_argument(2);
+            for segment in segments(parse_input_chars(INPUT), len) {
+                println!(
+                    "{:>8}  {}  {}",
+                    segment.offset, segment.marker, segment.payload
+                );
+            }
+        }
+        // Look for a marker of unique words rather than characters.
+        Some("words") => {
+            let len = marker_length_argument(2);
+            match tokens::find_token_marker(INPUT.split_whitespace(), len) {
+                Some(position) => println!("Marker of {len} unique words:\n{position}"),
+                None => println!("No marker of {len} unique words found"),
+            }
+        }
+        // Compare the strategies on a random stream of the given number of megabytes.
+        Some("bench") => {
+            let size = std::env::args().nth(2).map_or(16, |size| {
+                size.parse().expect("Stream size should be a number")
+            });
+            let buffer = unique::random_stream(size * 1024 * 1024, 26, 2022);
+            for len in [4, 14] {
+                for strategy in Strategy::ALL {
+                    let start = Instant::now();
+                    let count = strategy.markers(&buffer, len).count();
+                    println!(
+                        "{strategy:?} found {count} markers of length {len} in {size} MB in {:?}",
+                        start.elapsed()
+                    );
+                }
+            }
+        }
+        // Look for a marker of any other length, optionally choosing the strategy.
+        // The strategies work on bytes, so other text is searched character by character instead.
+        Some(_) => {
+            let len = marker_length_argument(1);
+            let strategy = std::env::args().nth(2).map_or(Strategy::LastSeen, |name| {
+                Strategy::from_name(&name).unwrap_or_else(|| panic!("Unknown strategy {name:?}"))
+            });
+            let datastream = parse_input_chars(INPUT);
+            let position = if datastream.is_ascii() {
+                strategy.markers(datastream.as_bytes(), len).next()
+            } else {
+                tokens::find_char_marker(datastream, len)
+            };
+            match position {
+                Some(position) => println!("Marker of length {len}:\n{position}"),
+                None => println!("No marker of length {len} found"),
+            }
+        }
+        None => {}
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::stream::{self, Marker};
+    use crate::tokens;
+    use crate::unique::{self, Strategy, all_unique};
+    use crate::{MarkerMode, Segment};
+
+    /// Reader handing out a datastream in chunks of at most `chunk_size` bytes.
+    struct ChunkedReader<'a> {
+        data: &'a [u8],
+        chunk_size: usize,
+    }
+
+    impl std::io::Read for ChunkedReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.data.len().min(self.chunk_size).min(buf.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn stream_across_chunks() {
+        let data = b"mjqjpqmgbljsphdztnvjfqwrcgsmlb\n";
+        for chunk_size in 1..=7 {
+            let reader = ChunkedReader { data, chunk_size };
+            let markers = stream::scan(reader, &[14, 0, 4, 26]).unwrap();
+            assert_eq!(
+                markers,
+                vec![
+                    Marker {
+                        len: 4,
+                        offset: 7,
+                        byte_offset: 7
+                    },
+                    Marker {
+                        len: 14,
+                        offset: 19,
+                        byte_offset: 19
+                    }
+                ]
+            );
+        }
+        // A length of 0 never makes up a marker.
+        let reader = ChunkedReader {
+            data,
+            chunk_size: 1,
+        };
+        assert_eq!(
+            stream::scan(reader, &[0, 4]).unwrap(),
+            vec![Marker {
+                len: 4,
+                offset: 7,
+                byte_offset: 7
+            }]
+        );
+        let reader = ChunkedReader {
+            data,
+            chunk_size: 1,
+        };
+        assert!(stream::scan(reader, &[0]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn all_markers() {
+        let buffer = b"abcabcdd";
+        let overlapping: Vec<usize> = crate::markers(buffer, 3, MarkerMode::Overlapping).collect();
+        assert_eq!(overlapping, vec![3, 4, 5, 6, 7]);
+        let separate: Vec<usize> = crate::markers(buffer, 3, MarkerMode::NonOverlapping).collect();
+        assert_eq!(separate, vec![3, 6]);
+        assert_eq!(
+            crate::markers(buffer, 5, MarkerMode::Overlapping).count(),
+            0
+        );
+        assert_eq!(crate::solve_part_two("abcabc"), None);
+    }
+
+    #[test]
+    fn segmentation() {
+        let segments = crate::segments("aaabcdefabcdd", 3);
+        assert_eq!(
+            segments,
+            vec![
+                Segment {
+                    offset: 2,
+                    marker: "abc",
+                    payload: ""
+                },
+                Segment {
+                    offset: 5,
+                    marker: "def",
+                    payload: ""
+                },
+                Segment {
+                    offset: 8,
+                    marker: "abc",
+                    payload: "dd"
+                },
+            ]
+        );
+        let segments = crate::segments("abcaabbbcab", 3);
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].payload, "aabb");
+    }
+
+    #[test]
+    fn strategies_agree() {
+        for seed in 1..=50 {
+            let alphabet = (seed % 26) as u8 + 1;
+            let buffer = unique::random_stream(500, alphabet, seed);
+            for len in 0..=27 {
+                let expected: Vec<usize> = buffer
+                    .windows(len.max(1))
+                    .enumerate()
+                    .filter(|(_, window)| len > 0 && all_unique(window))
+                    .map(|(i, _)| i + len)
+                    .collect();
+                for strategy in Strategy::ALL {
+                    let found: Vec<usize> = strategy.markers(&buffer, len).collect();
+                    assert_eq!(found, expected, "{strategy:?}, seed {seed}, length {len}");
+                }
+            }
+        }
+        // Streams with other bytes than lowercase letters fall back to comparing pairs.
+        let found: Vec<usize> = Strategy::Bitmask.markers(b"aB1aB", 3).collect();
+        assert_eq!(found, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn multibyte_characters() {
+        // Every `é` takes two bytes, but counts as a single character.
+        assert_eq!(crate::solve_part_one("ééàéàüöé"), Some(7));
+        assert_eq!(tokens::find_char_marker("日本日本語", 3), Some(5));
+        assert_eq!(tokens::find_char_marker("ééé", 2), None);
+
+        // Listing and segmenting count characters too, and never split one.
+        let datastream = "ééàéàüöéüàé";
+        let all: Vec<usize> =
+            tokens::char_markers(datastream, 3, MarkerMode::Overlapping).collect();
+        assert_eq!(all, vec![6, 7, 8, 9, 10, 11]);
+        let separate: Vec<usize> =
+            tokens::char_markers(datastream, 3, MarkerMode::NonOverlapping).collect();
+        assert_eq!(separate, vec![6, 9]);
+        assert_eq!(
+            crate::segments(datastream, 3),
+            vec![
+                Segment {
+                    offset: 3,
+                    marker: "éàü",
+                    payload: ""
+                },
+                Segment {
+                    offset: 6,
+                    marker: "öéü",
+                    payload: "àé"
+                },
+            ]
+        );
+
+        // Streaming reads multibyte characters split across chunks.
+        let data = "日本日本語\nあいあいうえお".as_bytes();
+        for chunk_size in 1..=7 {
+            let reader = ChunkedReader { data, chunk_size };
+            let markers = stream::scan(reader, &[3, 4]).unwrap();
+            assert_eq!(
+                markers,
+                vec![
+                    Marker {
+                        len: 3,
+                        offset: 5,
+                        byte_offset: 15
+                    },
+                    Marker {
+                        len: 4,
+                        offset: 7,
+                        byte_offset: 19
+                    }
+                ]
+            );
+        }
+        let truncated = &"日本語".as_bytes()[..7];
+        let reader = ChunkedReader {
+            data: truncated,
+            chunk_size: 4,
+        };
+        assert!(stream::scan(reader, &[4]).is_err());
+    }
+
+    #[test]
+    fn word_tokens() {
+        let words = "the cat saw the dog see the cat".split_whitespace();
+        assert_eq!(tokens::find_token_marker(words, 4), Some(5));
+        assert_eq!(tokens::find_token_marker([1, 2, 1, 3, 4], 3), Some(4));
+        assert_eq!(tokens::find_token_marker(Vec::<u64>::new(), 1), None);
+    }
+
+    #[test]
+    fn find_marker_matches_brute_force() {
+        let buffer = b"nznrnfrfntjfmvfwmzdfjlvtqnbhcprsgaabbccddeeffgghhiijjkkllmmnnoopqrstuvwxyz";
+        for len in 1..=30 {
+            let expected = buffer.windows(len).position(all_unique).map(|i| i + len);
+            assert_eq!(
+                crate::find_marker(buffer, len),
+                expected,
+                "window length {len}"
+            );
+        }
+        assert_eq!(crate::find_marker(buffer, 0), None);
+        assert_eq!(crate::find_marker(b"aaaa", 2), None);
+        assert_eq!(crate::find_marker(b"", 4), None);
+    }
+
     #[test]
     fn part1_example1() {
         let example_input = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
         let answer = crate::solve_part_one(example_input);
-        assert_eq!(answer, 7);
+        assert_eq!(answer, Some(7));
     }
 
     #[test]
     fn part1_example2() {
         let example_input = "bvwbjplbgvbhsrlpgdmjqwftvncz";
         let answer = crate::solve_part_one(example_input);
-        assert_eq!(answer, 5);
+        assert_eq!(answer, Some(5));
     }
 
     #[test]
     fn part1_example3() {
         let example_input = "nppdvjthqldpwncqszvftbrmjlhg";
         let answer = crate::solve_part_one(example_input);
-        assert_eq!(answer, 6);
+        assert_eq!(answer, Some(6));
     }
 
     #[test]
     fn part1_example4() {
         let example_input = "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg";
         let answer = crate::solve_part_one(example_input);
-        assert_eq!(answer, 10);
+        assert_eq!(answer, Some(10));
     }
 
     #[test]
     fn part1_example5() {
         let example_input = "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw";
         let answer = crate::solve_part_one(example_input);
-        assert_eq!(answer, 11);
+        assert_eq!(answer, Some(11));
     }
 
     #[test]
     fn part2_example1() {
         let example_input = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
         let answer = crate::solve_part_two(example_input);
-        assert_eq!(answer, 19);
+        assert_eq!(answer, Some(19));
     }
 
     #[test]
     fn part2_example2() {
         let example_input = "bvwbjplbgvbhsrlpgdmjqwftvncz";
         let answer = crate::solve_part_two(example_input);
-        assert_eq!(answer, 23);
+        assert_eq!(answer, Some(23));
     }
 
     #[test]
     fn part2_example3() {
         let example_input = "nppdvjthqldpwncqszvftbrmjlhg";
         let answer = crate::solve_part_two(example_input);
-        assert_eq!(answer, 23);
+        assert_eq!(answer, Some(23));
     }
 
     #[test]
     fn part2_example4() {
         let example_input = "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg";
         let answer = crate::solve_part_two(example_input);
-        assert_eq!(answer, 29);
+        assert_eq!(answer, Some(29));
     }
 
     #[test]
     fn part2_example5() {
         let example_input = "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw";
         let answer = crate::solve_part_two(example_input);
-        assert_eq!(answer, 26);
+        assert_eq!(answer, Some(26));
     }
 }
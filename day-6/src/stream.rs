@@ -0,0 +1,81 @@
+//! Marker detection over datastreams that are read in chunks, e.g. from a file or stdin.
+
+use std::io::{self, ErrorKind, Read};
+
+use crate::Detector;
+use crate::tokens::TokenDetector;
+
+/// A marker found in a datastream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Marker {
+    /// Number of unique characters that make up the marker.
+    pub len: usize,
+    /// Number of characters read from the source up to and including the end of the marker.
+    pub offset: u64,
+    /// Number of bytes read from the source up to and including the end of the marker,
+    /// for seeking to it in the source.
+    pub byte_offset: u64,
+}
+
+/// Reads the UTF-8 datastream chunk by chunk and reports the first marker of each of the given lengths,
+/// in the order they end in the stream. Reading stops as soon as every marker is found.
+/// Line breaks are not part of the datastream, but are included in the offsets.
+/// A marker consists of at least one character, so nothing is reported for a length of 0.
+pub fn scan(mut reader: impl Read, lengths: &[usize]) -> io::Result<Vec<Marker>> {
+    let mut detectors: Vec<TokenDetector<char>> =
+        lengths.iter().map(|&len| TokenDetector::new(len)).collect();
+    let mut markers = Vec::new();
+    // Lengths of 0 count as found from the start, so they are never reported.
+    let mut found: Vec<bool> = lengths.iter().map(|&len| len == 0).collect();
+    let wanted = found.iter().filter(|&&found| !found).count();
+    let mut offset = 0;
+    let mut byte_offset = 0;
+    let mut chunk = vec![0; 64 * 1024];
+    // Bytes at the end of the previous chunk that only make up part of a character.
+    let mut pending = 0;
+    while markers.len() < wanted {
+        let read = match reader.read(&mut chunk[pending..]) {
+            Ok(0) if pending > 0 => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "datastream ends inside a character",
+                ));
+            }
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        let available = pending + read;
+        let text = match std::str::from_utf8(&chunk[..available]) {
+            Ok(text) => text,
+            // The chunk ends inside a character, which is completed by the next read.
+            Err(err) if err.error_len().is_none() => {
+                std::str::from_utf8(&chunk[..err.valid_up_to()])
+                    .expect("Should be valid up to the incomplete character")
+            }
+            Err(err) => return Err(io::Error::new(ErrorKind::InvalidData, err)),
+        };
+        let decoded = text.len();
+        for c in text.chars() {
+            offset += 1;
+            byte_offset += c.len_utf8() as u64;
+            if c == '\n' || c == '\r' {
+                continue;
+            }
+            for (idx, detector) in detectors.iter_mut().enumerate() {
+                if detector.push(c) && !found[idx] {
+                    found[idx] = true;
+                    markers.push(Marker {
+                        len: lengths[idx],
+                        offset,
+                        byte_offset,
+                    });
+                }
+            }
+        }
+        chunk.copy_within(decoded..available, 0);
+        pending = available - decoded;
+    }
+    Ok(markers)
+}
@@ -0,0 +1,67 @@
+//! Marker detection over datastreams of arbitrary tokens, such as chars or words.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::{Detector, MarkerMode, Markers, find_marker};
+
+/// Same approach as `MarkerDetector`, but remembers the last position of each token in a map,
+/// so it works for any alphabet instead of only single bytes.
+pub struct TokenDetector<T> {
+    len: usize,
+    last_seen: HashMap<T, usize>,
+    /// Position of the first token of the run of unique tokens.
+    start: usize,
+    pushed: usize,
+}
+
+impl<T: Eq + Hash> Detector for TokenDetector<T> {
+    type Token = T;
+
+    fn new(len: usize) -> Self {
+        Self {
+            len,
+            last_seen: HashMap::new(),
+            start: 0,
+            pushed: 0,
+        }
+    }
+
+    fn push(&mut self, token: T) -> bool {
+        if let Some(&previous) = self.last_seen.get(&token)
+            && previous >= self.start
+        {
+            self.start = previous + 1;
+        }
+        self.last_seen.insert(token, self.pushed);
+        self.pushed += 1;
+        self.pushed - self.start >= self.len
+    }
+}
+
+/// Returns the number of tokens processed up to and including the first window of `len` unique tokens.
+pub fn find_token_marker<T: Eq + Hash>(
+    tokens: impl IntoIterator<Item = T>,
+    len: usize,
+) -> Option<usize> {
+    Markers::<_, TokenDetector<T>>::new(tokens.into_iter(), len, MarkerMode::Overlapping).next()
+}
+
+/// Returns the number of characters processed up to and including each window of `len` unique characters.
+pub fn char_markers(
+    datastream: &str,
+    len: usize,
+    mode: MarkerMode,
+) -> Markers<std::str::Chars<'_>, TokenDetector<char>> {
+    Markers::new(datastream.chars(), len, mode)
+}
+
+/// Returns the number of characters processed up to and including the first window of `len` unique characters.
+/// ASCII datastreams take the faster byte based path, as every character is a single byte there.
+pub fn find_char_marker(datastream: &str, len: usize) -> Option<usize> {
+    if datastream.is_ascii() {
+        find_marker(datastream.as_bytes(), len)
+    } else {
+        find_token_marker(datastream.chars(), len)
+    }
+}
@@ -0,0 +1,107 @@
+//! Interchangeable strategies to find windows of unique bytes in a datastream.
+
+use crate::{MarkerMode, markers};
+
+/// Straightforward check comparing every pair of bytes in the window.
+pub fn all_unique(window: &[u8]) -> bool {
+    for i in 0..window.len() {
+        for j in i + 1..window.len() {
+            if window[i] == window[j] {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Ways to find the windows of unique bytes in a datastream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Compares every pair of bytes in every window using `all_unique`.
+    Naive,
+    /// Keeps a rolling bitmask of the letters in the window, only for streams of lowercase letters.
+    Bitmask,
+    /// Keeps track of where each byte was last seen, see `MarkerDetector`.
+    LastSeen,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 3] = [Strategy::Naive, Strategy::Bitmask, Strategy::LastSeen];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "naive" => Some(Strategy::Naive),
+            "bitmask" => Some(Strategy::Bitmask),
+            "last-seen" => Some(Strategy::LastSeen),
+            _ => None,
+        }
+    }
+
+    /// Returns the end positions of all (overlapping) windows of `len` unique bytes.
+    pub fn markers<'a>(self, buffer: &'a [u8], len: usize) -> Box<dyn Iterator<Item = usize> + 'a> {
+        match self {
+            Strategy::Bitmask if buffer.iter().all(u8::is_ascii_lowercase) => {
+                Box::new(BitmaskMarkers {
+                    buffer,
+                    len,
+                    position: 0,
+                    mask: 0,
+                })
+            }
+            // Streams with other bytes than lowercase letters do not fit in the bitmask.
+            Strategy::Naive | Strategy::Bitmask => Box::new(
+                buffer
+                    .windows(len.max(1))
+                    .enumerate()
+                    .filter(move |(_, window)| len > 0 && all_unique(window))
+                    .map(move |(i, _)| i + len),
+            ),
+            Strategy::LastSeen => Box::new(markers(buffer, len, MarkerMode::Overlapping)),
+        }
+    }
+}
+
+/// Iterator over the windows of unique letters in a stream of lowercase letters.
+/// Every letter in the window toggles its own bit in the mask, so a letter that occurs twice cancels itself out.
+/// The window therefore only holds unique letters when the mask has as many bits set as the window is long.
+struct BitmaskMarkers<'a> {
+    buffer: &'a [u8],
+    len: usize,
+    position: usize,
+    mask: u32,
+}
+
+impl Iterator for BitmaskMarkers<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.len == 0 {
+            return None;
+        }
+        while let Some(&byte) = self.buffer.get(self.position) {
+            self.mask ^= 1 << (byte - b'a');
+            if self.position >= self.len {
+                self.mask ^= 1 << (self.buffer[self.position - self.len] - b'a');
+            }
+            self.position += 1;
+            if self.position >= self.len && self.mask.count_ones() as usize == self.len {
+                return Some(self.position);
+            }
+        }
+        None
+    }
+}
+
+/// Generates a pseudo-random stream of lowercase letters drawn from the first `alphabet` letters,
+/// using a xorshift generator so results are reproducible for a given seed.
+pub fn random_stream(len: usize, alphabet: u8, seed: u64) -> Vec<u8> {
+    let mut state = seed.max(1);
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            b'a' + (state % u64::from(alphabet)) as u8
+        })
+        .collect()
+}
@@ -0,0 +1,80 @@
+//! Conversion between the reconstructed filesystem and real directories on disk.
+
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+use crate::Directory;
+
+/// Recreates the directory tree below `target`, creating every file as a sparse file of its recorded size.
+/// Entries whose name is not a plain file name are rejected before anything is written,
+/// so nothing ends up outside `target` and no partial tree is left behind.
+pub fn materialize(dir: &Directory, target: &Path) -> io::Result<()> {
+    check_names(dir)?;
+    write_tree(dir, target)
+}
+
+fn check_names(dir: &Directory) -> io::Result<()> {
+    for name in dir.files.keys() {
+        check_name(name)?;
+    }
+    for (name, subdir) in &dir.directories {
+        check_name(name)?;
+        check_names(subdir)?;
+    }
+    Ok(())
+}
+
+fn write_tree(dir: &Directory, target: &Path) -> io::Result<()> {
+    fs::create_dir_all(target)?;
+    for (name, &size) in &dir.files {
+        File::create(target.join(name))?.set_len(size)?;
+    }
+    for (name, subdir) in &dir.directories {
+        write_tree(subdir, &target.join(name))?;
+    }
+    Ok(())
+}
+
+/// Names from the log could be absolute or contain `..`, which `Path::join` would follow
+/// out of the target directory, so only plain file names are accepted.
+fn check_name(name: &str) -> io::Result<()> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{name:?} is not a plain file name"),
+        ));
+    }
+    Ok(())
+}
+
+/// Generates the terminal output of exploring the directory at `root` with `cd` and `ls`,
+/// in the format of the puzzle input. Entries that are neither files nor directories are skipped.
+pub fn transcript(root: &Path) -> io::Result<String> {
+    let mut output = String::from("$ cd /\n");
+    write_transcript(root, &mut output)?;
+    Ok(output)
+}
+
+fn write_transcript(path: &Path, output: &mut String) -> io::Result<()> {
+    let mut entries = fs::read_dir(path)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    let mut subdirs = Vec::new();
+    output.push_str("$ ls\n");
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            output.push_str(&format!("dir {name}\n"));
+            subdirs.push((name, entry.path()));
+        } else if file_type.is_file() {
+            output.push_str(&format!("{} {name}\n", entry.metadata()?.len()));
+        }
+    }
+    for (name, subdir) in subdirs {
+        output.push_str(&format!("$ cd {name}\n"));
+        write_transcript(&subdir, output)?;
+        output.push_str("$ cd ..\n");
+    }
+    Ok(())
+}
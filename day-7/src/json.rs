@@ -0,0 +1,28 @@
+//! JSON export and import of the reconstructed directory tree.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Serialize, Serializer};
+
+use crate::Directory;
+
+/// Writes the directory tree as JSON, including the total size of every directory.
+pub fn export(dir: &Directory) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(dir)
+}
+
+/// Loads a directory tree from JSON. Total sizes are computed again from the files,
+/// so they are correct even if the JSON was edited by hand.
+pub fn import(json: &str) -> serde_json::Result<Directory> {
+    let mut dir: Directory = serde_json::from_str(json)?;
+    dir.compute_sizes();
+    Ok(dir)
+}
+
+/// Serializes a map with its entries sorted by name, so exports of the same tree are identical.
+pub fn sorted<S: Serializer, V: Serialize>(
+    map: &HashMap<String, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
@@ -1,12 +1,85 @@
 // Advent of Code 2022 - Day 7: No Space Left On Device
 
-use std::collections::HashMap;
+mod disk;
+mod json;
+mod planner;
+mod query;
+mod report;
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use planner::DeletionPlanner;
+use query::Entry;
+use report::SortOrder;
+use serde::{Deserialize, Serialize};
 
 const INPUT: &str = include_str!("./input.txt");
 
 struct Filesystem {
     root: Directory,
     current_path: Vec<String>,
+    /// Inconsistencies found in the terminal output while building the filesystem.
+    inconsistencies: Vec<Inconsistency>,
+}
+
+/// Parts of the terminal output that do not agree with what was seen before.
+#[derive(Debug, PartialEq, Eq)]
+enum Inconsistency {
+    /// A `cd` into a directory that was not listed by a `dir` entry of its parent.
+    CdIntoUndeclared { line: usize, path: String },
+    /// A directory was listed again, with different entries than before.
+    ListingChanged { line: usize, path: String },
+    /// A file was listed again, with a different size than before.
+    FileSizeChanged {
+        line: usize,
+        path: String,
+        old: u64,
+        new: u64,
+    },
+    /// A line that is neither a command nor an entry of a listing.
+    InvalidLine { line: usize },
+}
+
+impl Inconsistency {
+    fn line(&self) -> usize {
+        match self {
+            Inconsistency::CdIntoUndeclared { line, .. }
+            | Inconsistency::ListingChanged { line, .. }
+            | Inconsistency::FileSizeChanged { line, .. }
+            | Inconsistency::InvalidLine { line } => *line,
+        }
+    }
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Inconsistency::CdIntoUndeclared { line, path } => {
+                write!(f, "line {line}: cd into {path}, which was never listed")
+            }
+            Inconsistency::ListingChanged { line, path } => {
+                write!(f, "line {line}: {path} is listed with different entries")
+            }
+            Inconsistency::FileSizeChanged {
+                line,
+                path,
+                old,
+                new,
+            } => write!(f, "line {line}: size of {path} changed from {old} to {new}"),
+            Inconsistency::InvalidLine { line } => write!(f, "line {line}: unrecognized output"),
+        }
+    }
+}
+
+/// Entries printed by a single `ls` command.
+#[derive(Default)]
+struct Listing {
+    /// Line number of the `ls` command.
+    line: usize,
+    files: HashMap<String, u64>,
+    directories: BTreeSet<String>,
 }
 
 impl Filesystem {
@@ -14,6 +87,7 @@ impl Filesystem {
         Self {
             root: Directory::new("/"),
             current_path: Vec::new(),
+            inconsistencies: Vec::new(),
         }
     }
 
@@ -26,32 +100,122 @@ impl Filesystem {
         dir
     }
 
+    /// Returns the path of the current directory, or of an entry in it, e.g. `/a/e`.
+    fn path_of(&self, name: Option<&str>) -> String {
+        let mut path = String::new();
+        for part in self.current_path.iter().map(String::as_str).chain(name) {
+            path.push('/');
+            path.push_str(part);
+        }
+        if path.is_empty() {
+            path.push('/');
+        }
+        path
+    }
+
     /// Handle a 'cd' command, updating the current path.
-    fn cd(&mut self, arg: &str) {
+    fn cd(&mut self, arg: &str, line: usize) {
         match arg {
             "/" => self.current_path.clear(),
             ".." => {
                 self.current_path.pop();
             }
             name => {
+                if !self.current_dir_mut().directories.contains_key(name) {
+                    let path = self.path_of(Some(name));
+                    self.inconsistencies
+                        .push(Inconsistency::CdIntoUndeclared { line, path });
+                }
                 self.current_dir_mut().get_or_create_dir_mut(name);
                 self.current_path.push(name.to_string());
             }
         }
     }
 
-    /// Add a file to the current directory.
-    fn add_file(&mut self, name: &str, size: u64) {
-        self.current_dir_mut().files.insert(name.to_string(), size);
+    /// Record the entries listed by an 'ls' command in the current directory,
+    /// comparing them with an earlier listing of the same directory.
+    ///
+    /// The latest listing shows the directory as it is now, so it replaces the entries of an earlier
+    /// one: files and directories that are no longer listed are dropped, together with their contents.
+    /// Directories entered with `cd` without being listed are kept, as entering them shows they exist.
+    /// They were already reported as `CdIntoUndeclared`, so they are left out of the comparison.
+    fn record_listing(&mut self, listing: Listing) {
+        let line = listing.line;
+        let current = self.path_of(None);
+        let entered: HashSet<String> = self
+            .inconsistencies
+            .iter()
+            .filter_map(|inconsistency| match inconsistency {
+                Inconsistency::CdIntoUndeclared { path, .. } => path.rsplit_once('/'),
+                _ => None,
+            })
+            .filter(|&(parent, _)| parent == current.trim_end_matches('/'))
+            .map(|(_, name)| name.to_string())
+            .collect();
+        let mut found = Vec::new();
+        let dir = self.current_dir_mut();
+        if dir.listed {
+            for (name, &new) in &listing.files {
+                if let Some(&old) = dir.files.get(name)
+                    && old != new
+                {
+                    found.push((Some(name.clone()), Some((old, new))));
+                }
+            }
+            let same_files = dir.files.len() == listing.files.len()
+                && listing
+                    .files
+                    .keys()
+                    .all(|name| dir.files.contains_key(name));
+            let old_dirs: BTreeSet<&String> = dir
+                .directories
+                .keys()
+                .filter(|name| !entered.contains(*name))
+                .collect();
+            let new_dirs: BTreeSet<&String> = listing
+                .directories
+                .iter()
+                .filter(|name| !entered.contains(*name))
+                .collect();
+            if !same_files || old_dirs != new_dirs {
+                found.push((None, None));
+            }
+        }
+        dir.listed = true;
+        dir.files = listing.files;
+        dir.directories
+            .retain(|name, _| listing.directories.contains(name) || entered.contains(name));
+        for name in &listing.directories {
+            dir.get_or_create_dir_mut(name);
+        }
+        for (name, sizes) in found {
+            let path = self.path_of(name.as_deref());
+            self.inconsistencies.push(match sizes {
+                Some((old, new)) => Inconsistency::FileSizeChanged {
+                    line,
+                    path,
+                    old,
+                    new,
+                },
+                None => Inconsistency::ListingChanged { line, path },
+            });
+        }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 struct Directory {
-    #[allow(unused)]
     name: String,
+    #[serde(serialize_with = "json::sorted")]
     files: HashMap<String, u64>,
+    #[serde(serialize_with = "json::sorted")]
     directories: HashMap<String, Directory>,
+    /// Whether the contents of the directory were printed by an `ls` command.
+    #[serde(default)]
+    listed: bool,
+    /// Total size of the files in the directory and its subdirectories, set by `compute_sizes`.
+    #[serde(rename = "size", default)]
+    total_size: u64,
 }
 
 impl Directory {
@@ -60,13 +224,27 @@ impl Directory {
             name: name.into(),
             files: HashMap::new(),
             directories: HashMap::new(),
+            listed: false,
+            total_size: 0,
         }
     }
 
+    /// Returns the total size as cached by the last call to `compute_sizes`.
     fn size(&self) -> u64 {
+        self.total_size
+    }
+
+    /// Computes and caches the total size of this directory and all its subdirectories,
+    /// visiting every subdirectory before its parent so each size is computed only once.
+    fn compute_sizes(&mut self) -> u64 {
         let file_sum: u64 = self.files.values().copied().sum();
-        let dir_sum: u64 = self.directories.values().map(Directory::size).sum();
-        file_sum + dir_sum
+        let dir_sum: u64 = self
+            .directories
+            .values_mut()
+            .map(Directory::compute_sizes)
+            .sum();
+        self.total_size = file_sum + dir_sum;
+        self.total_size
     }
 
     fn get_or_create_dir_mut(&mut self, name: &str) -> &mut Directory {
@@ -74,64 +252,218 @@ impl Directory {
             .entry(name.to_string())
             .or_insert_with(|| Directory::new(name))
     }
-
-    fn collect_dir_sizes(&self) -> Vec<u64> {
-        let mut sizes = Vec::new();
-        sizes.push(self.size());
-        // Recursively call the function on all subdirectories
-        // Extend the current vector with vectors returned from the recursive calls
-        for dir in self.directories.values() {
-            sizes.extend(dir.collect_dir_sizes());
-        }
-        sizes
-    }
 }
 
-fn parse_input(input: &str) -> Directory {
+fn parse_input(input: &str) -> Filesystem {
     let mut fs = Filesystem::new();
-    for line in input.trim().lines() {
+    let mut listing: Option<Listing> = None;
+    for (idx, line) in input.trim().lines().enumerate() {
+        let line_number = idx + 1;
+        if line.starts_with('$')
+            && let Some(listing) = listing.take()
+        {
+            fs.record_listing(listing);
+        }
         if let Some(arg) = line.strip_prefix("$ cd ") {
-            fs.cd(arg);
-        } else if line.starts_with("$ ls") || line.starts_with("dir ") {
-        } else {
+            fs.cd(arg, line_number);
+        } else if line == "$ ls" {
+            listing = Some(Listing {
+                line: line_number,
+                ..Listing::default()
+            });
+        } else if let Some(listing) = listing.as_mut()
+            && let Some(name) = line.strip_prefix("dir ")
+        {
+            listing.directories.insert(name.to_string());
+        } else if let Some(listing) = listing.as_mut()
+            && let Some((size, name)) = line.split_once(' ')
+            && let Ok(size) = size.parse()
+        {
             // file: "<size> <name>"
-            let mut parts = line.split_whitespace();
-            let size: u64 = parts.next().unwrap().parse().unwrap();
-            let name: &str = parts.next().unwrap();
-            fs.add_file(name, size);
+            listing.files.insert(name.to_string(), size);
+        } else {
+            fs.inconsistencies
+                .push(Inconsistency::InvalidLine { line: line_number });
         }
     }
-    fs.root
+    if let Some(listing) = listing {
+        fs.record_listing(listing);
+    }
+    // Listings are only checked once they end, so put everything back in the order of the output.
+    fs.inconsistencies.sort_by_key(Inconsistency::line);
+    fs.root.compute_sizes();
+    fs
 }
 
-fn solve_part_one(input: &str) -> u64 {
-    let fs = parse_input(input);
-    let dir_sizes = fs.collect_dir_sizes();
-    dir_sizes.into_iter().filter(|&size| size <= 100_000).sum()
+fn solve_part_one(fs: &Filesystem) -> u64 {
+    fs.find_dirs(|size| size <= 100_000)
+        .into_iter()
+        .map(|(_, size)| size)
+        .sum()
 }
 
-fn solve_part_two(input: &str) -> u64 {
-    let fs = parse_input(input);
-    let used_space = fs.size();
-    let dir_sizes = fs.collect_dir_sizes();
-    let current_free_disk_space = 70_000_000 - used_space;
-    let target_size = 30_000_000 - current_free_disk_space;
-    dir_sizes
-        .into_iter()
-        .filter(|&size| size >= target_size)
-        .min()
-        .unwrap()
+fn solve_part_two(fs: &Filesystem) -> u64 {
+    let planner = DeletionPlanner {
+        disk_size: 70_000_000,
+        required: 30_000_000,
+    };
+    planner
+        .smallest_directory(fs)
+        .expect("Disk should be large enough for the update")
+        .freed
 }
 
 fn main() {
-    let part_one_answer = solve_part_one(INPUT);
+    let fs = parse_input(INPUT);
+    for inconsistency in &fs.inconsistencies {
+        println!("Warning: {inconsistency}");
+    }
+    let part_one_answer = solve_part_one(&fs);
     println!("Part one:\n{part_one_answer}");
-    let part_two_answer = solve_part_two(INPUT);
+    let part_two_answer = solve_part_two(&fs);
     println!("Part two:\n{part_two_answer}");
+    // Optionally print the filesystem as a tree, or as a `du` table sorted by `name` or `size`,
+    // limited to the depth given as last argument.
+    let args: Vec<String> = std::env::args().collect();
+    let depth_argument = |position: usize| {
+        args.get(position)
+            .map(|depth| depth.parse().expect("Depth should be a number"))
+    };
+    match args.get(1).map(String::as_str) {
+        Some("tree") => print!("{}", report::tree(&fs.root, depth_argument(2))),
+        Some("du") => {
+            let order = match args.get(2).map(String::as_str) {
+                Some("size") => SortOrder::Size,
+                _ => SortOrder::Name,
+            };
+            print!("{}", report::du(&fs.root, order, depth_argument(3)));
+        }
+        // Look up a single path, e.g. `/a/e`.
+        Some("get") => {
+            let path = args.get(2).expect("Should be given a path");
+            match fs.get(path) {
+                Some(Entry::File { size }) => println!("{path} (file, size={size})"),
+                Some(Entry::Directory(dir)) => println!("{path} (dir, size={})", dir.size()),
+                None => println!("{path} does not exist"),
+            }
+        }
+        // Find all paths matching a glob pattern, e.g. `**/*.log`.
+        Some("find") => {
+            for (path, entry) in fs.glob(args.get(2).expect("Should be given a pattern")) {
+                println!("{}\t{path}", entry.size());
+            }
+        }
+        // List the largest files, ten by default.
+        Some("largest") => {
+            let n = args.get(2).map_or(10, |n| {
+                n.parse().expect("Number of files should be a number")
+            });
+            for (path, size) in fs.largest_files(n) {
+                println!("{size}\t{path}");
+            }
+        }
+        // Plan deletions for a disk of a given size, so the required space becomes available.
+        Some("plan") => {
+            let size_argument = |position: usize, default| {
+                args.get(position).map_or(default, |size: &String| {
+                    size.parse().expect("Size should be a number")
+                })
+            };
+            let planner = DeletionPlanner {
+                disk_size: size_argument(2, 70_000_000),
+                required: size_argument(3, 30_000_000),
+            };
+            let plans = [
+                ("Smallest directory", planner.smallest_directory(&fs)),
+                ("Smallest set of directories", planner.smallest_set(&fs)),
+            ];
+            for (name, plan) in plans {
+                match plan {
+                    Some(plan) => {
+                        println!("{name} frees {}: {}", plan.freed, plan.paths.join(", "))
+                    }
+                    None => println!("{name}: the disk is too small for the required space"),
+                }
+            }
+        }
+        // Recreate the filesystem on disk, in a temporary directory unless a directory is given.
+        Some("materialize") => {
+            let target = args.get(2).map_or_else(
+                || std::env::temp_dir().join(format!("day-7-{}", std::process::id())),
+                PathBuf::from,
+            );
+            match disk::materialize(&fs.root, &target) {
+                Ok(()) => println!("Filesystem created in {}", target.display()),
+                Err(err) => println!("Could not create filesystem in {}: {err}", target.display()),
+            }
+        }
+        // Print the terminal output of exploring an existing directory.
+        Some("transcript") => {
+            let root = args.get(2).expect("Should be given a directory");
+            match disk::transcript(Path::new(root)) {
+                Ok(transcript) => print!("{transcript}"),
+                Err(err) => println!("Could not read {root}: {err}"),
+            }
+        }
+        // Write the filesystem as JSON, to stdout unless a file is given.
+        Some("export") => match json::export(&fs.root) {
+            Ok(json) => match args.get(2) {
+                Some(path) => {
+                    if let Err(err) = std::fs::write(path, json) {
+                        println!("Could not write {path}: {err}");
+                    }
+                }
+                None => println!("{json}"),
+            },
+            Err(err) => println!("Could not export filesystem: {err}"),
+        },
+        // Load a filesystem from JSON and show it as a tree.
+        Some("import") => {
+            let path = args.get(2).expect("Should be given a JSON file");
+            let imported = std::fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|json| json::import(&json).map_err(|err| err.to_string()));
+            match imported {
+                Ok(root) => print!("{}", report::tree(&root, None)),
+                Err(err) => println!("Could not import {path}: {err}"),
+            }
+        }
+        _ => {}
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::Inconsistency;
+    use crate::planner::{DeletionPlanner, Plan};
+    use crate::query::Entry;
+    use crate::report::{self, SortOrder};
+    use crate::{disk, json};
+
+    const EXAMPLE_INPUT: &str = "$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k";
+
     #[test]
     fn part1() {
         let example_input = "$ cd /
@@ -157,7 +489,7 @@ $ ls
 8033020 d.log
 5626152 d.ext
 7214296 k";
-        let answer = crate::solve_part_one(example_input);
+        let answer = crate::solve_part_one(&crate::parse_input(example_input));
         assert_eq!(answer, 95437);
     }
 
@@ -186,7 +518,447 @@ $ ls
 8033020 d.log
 5626152 d.ext
 7214296 k";
-        let answer = crate::solve_part_two(example_input);
+        let answer = crate::solve_part_two(&crate::parse_input(example_input));
         assert_eq!(answer, 24933642);
     }
+
+    #[test]
+    fn tree() {
+        let fs = crate::parse_input(EXAMPLE_INPUT);
+        assert_eq!(
+            report::tree(&fs.root, None),
+            "- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - d.ext (file, size=5626152)
+    - d.log (file, size=8033020)
+    - j (file, size=4060174)
+    - k (file, size=7214296)
+"
+        );
+        assert_eq!(
+            report::tree(&fs.root, Some(1)),
+            "- / (dir)
+  - a (dir)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+"
+        );
+    }
+
+    #[test]
+    fn du() {
+        let fs = crate::parse_input(EXAMPLE_INPUT);
+        assert_eq!(
+            report::du(&fs.root, SortOrder::Name, None),
+            "47M\t/\n93K\t/a\n584\t/a/e\n24M\t/d\n"
+        );
+        assert_eq!(
+            report::du(&fs.root, SortOrder::Size, Some(1)),
+            "47M\t/\n24M\t/d\n93K\t/a\n"
+        );
+        assert_eq!(report::human_size(1536), "1.5K");
+        assert_eq!(report::human_size(1025), "1.1K");
+    }
+
+    #[test]
+    fn cached_sizes() {
+        let fs = crate::parse_input(EXAMPLE_INPUT);
+        assert_eq!(fs.root.size(), 48381165);
+        assert_eq!(fs.root.directories["a"].size(), 94853);
+        assert_eq!(fs.root.directories["a"].directories["e"].size(), 584);
+        assert_eq!(fs.root.directories["d"].size(), 24933642);
+        let mut sizes: Vec<u64> = fs
+            .find_dirs(|_| true)
+            .into_iter()
+            .map(|(_, size)| size)
+            .collect();
+        sizes.sort();
+        assert_eq!(sizes, vec![584, 94853, 24933642, 48381165]);
+    }
+
+    #[test]
+    fn query_paths() {
+        let fs = crate::parse_input(EXAMPLE_INPUT);
+        assert!(matches!(fs.get("/a/e"), Some(Entry::Directory(dir)) if dir.size() == 584));
+        assert!(matches!(
+            fs.get("/d/k"),
+            Some(Entry::File { size: 7214296 })
+        ));
+        assert!(matches!(fs.get("/"), Some(Entry::Directory(dir)) if dir.name == "/"));
+        assert!(fs.get("/a/x").is_none());
+        assert!(fs.get("/b.txt/x").is_none());
+        let paths: Vec<String> = fs.walk().map(|(path, _)| path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/", "/a", "/a/e", "/a/e/i", "/a/f", "/a/g", "/a/h.lst", "/b.txt", "/c.dat", "/d",
+                "/d/d.ext", "/d/d.log", "/d/j", "/d/k"
+            ]
+        );
+    }
+
+    #[test]
+    fn query_searches() {
+        let fs = crate::parse_input(EXAMPLE_INPUT);
+        let paths = |pattern| -> Vec<String> {
+            fs.glob(pattern).into_iter().map(|(path, _)| path).collect()
+        };
+        assert_eq!(paths("**/*.log"), vec!["/d/d.log"]);
+        assert_eq!(paths("/*/?"), vec!["/a/e", "/a/f", "/a/g", "/d/j", "/d/k"]);
+        assert_eq!(paths("**/e/**"), vec!["/a/e", "/a/e/i"]);
+        assert_eq!(
+            fs.largest_files(2),
+            vec![
+                ("/b.txt".to_string(), 14848514),
+                ("/c.dat".to_string(), 8504156)
+            ]
+        );
+        assert_eq!(
+            fs.find_dirs(|size| size <= 100_000),
+            vec![("/a".to_string(), 94853), ("/a/e".to_string(), 584)]
+        );
+    }
+
+    #[test]
+    fn deletion_planner() {
+        let fs = crate::parse_input(EXAMPLE_INPUT);
+        let planner = DeletionPlanner {
+            disk_size: 70_000_000,
+            required: 30_000_000,
+        };
+        let single = Plan {
+            paths: vec!["/d".to_string()],
+            freed: 24933642,
+        };
+        assert_eq!(planner.smallest_directory(&fs), Some(single));
+        // Only `/d` frees enough space, as `/a` and `/a/e` are nested and too small.
+        assert_eq!(planner.smallest_set(&fs).unwrap().paths, vec!["/d"]);
+
+        let planner = DeletionPlanner {
+            disk_size: 48_400_000,
+            required: 100_000,
+        };
+        // 81165 needs to be freed: `/a` alone is 94853, while `/a/e` is too small.
+        assert_eq!(
+            planner.smallest_set(&fs),
+            Some(Plan {
+                paths: vec!["/a".to_string()],
+                freed: 94853
+            })
+        );
+        let planner = DeletionPlanner {
+            disk_size: 50_000_000,
+            required: 60_000_000,
+        };
+        assert_eq!(planner.smallest_directory(&fs), None);
+        let planner = DeletionPlanner {
+            disk_size: 70_000_000,
+            required: 1_000,
+        };
+        assert_eq!(
+            planner.smallest_set(&fs).unwrap().paths,
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn deletion_planner_multiple_directories() {
+        let example_input = "$ cd /
+$ ls
+dir a
+dir b
+dir c
+$ cd a
+$ ls
+600 x
+$ cd ..
+$ cd b
+$ ls
+500 y
+$ cd ..
+$ cd c
+$ ls
+dir d
+1200 z
+$ cd d
+$ ls
+100 w";
+        let fs = crate::parse_input(example_input);
+        let planner = DeletionPlanner {
+            disk_size: 3_000,
+            required: 2_000,
+        };
+        // 2400 used, so 600 unused and 1400 to free: `/c` alone frees only 1300 and `/a`, `/b`
+        // and `/c/d` together only 1200, while `/a` with `/c` frees 1900 and `/b` with `/c` 1800.
+        assert_eq!(
+            planner.smallest_set(&fs),
+            Some(Plan {
+                paths: vec!["/b".to_string(), "/c".to_string()],
+                freed: 1800
+            })
+        );
+        // The same tree with terabyte sizes, where memory must not grow with the number of bytes.
+        let scaled: String = example_input
+            .lines()
+            .map(|line| match line.split_once(' ') {
+                Some((size, name)) if size.parse::<u64>().is_ok() => {
+                    format!("{size}000000000 {name}\n")
+                }
+                _ => format!("{line}\n"),
+            })
+            .collect();
+        let fs = crate::parse_input(&scaled);
+        let planner = DeletionPlanner {
+            disk_size: 3_000_000_000_000,
+            required: 2_000_000_000_000,
+        };
+        assert_eq!(
+            planner.smallest_set(&fs),
+            Some(Plan {
+                paths: vec!["/b".to_string(), "/c".to_string()],
+                freed: 1_800_000_000_000
+            })
+        );
+    }
+
+    #[test]
+    fn deletion_planner_matches_brute_force() {
+        // Every sum that deleting directories none inside another can free.
+        fn freeable(dir: &crate::Directory) -> Vec<u64> {
+            let mut sums = vec![0];
+            for child in dir.directories.values() {
+                let child_sums = freeable(child);
+                sums = sums
+                    .iter()
+                    .flat_map(|sum| child_sums.iter().map(move |child_sum| sum + child_sum))
+                    .collect();
+            }
+            sums.push(dir.size());
+            sums
+        }
+        let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut next = |max: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % max
+        };
+        for _ in 0..50 {
+            let mut input = "$ cd /\n".to_string();
+            let mut depth = 0;
+            for dir in 0..12 {
+                input += &format!("$ ls\n{} f\ndir d{dir}\n$ cd d{dir}\n", 10 * next(50) + 1);
+                depth += 1;
+                for _ in 0..next(depth + 1) {
+                    input += "$ cd ..\n";
+                    depth -= 1;
+                }
+            }
+            input += "$ ls\n7 f\n";
+            let fs = crate::parse_input(&input);
+            let used = fs.root.size();
+            let planner = DeletionPlanner {
+                disk_size: used + 10,
+                required: 10 + next(used),
+            };
+            let target = planner.required - 10;
+            let expected = freeable(&fs.root)
+                .into_iter()
+                .filter(|&sum| sum >= target)
+                .min();
+            let plan = planner.smallest_set(&fs).unwrap();
+            assert_eq!(Some(plan.freed), expected, "{input}");
+            let deleted: u64 = plan
+                .paths
+                .iter()
+                .map(|path| fs.get(path).unwrap().size())
+                .sum();
+            assert_eq!(deleted, plan.freed, "{input}");
+        }
+    }
+
+    #[test]
+    fn disk_round_trip() {
+        let fs = crate::parse_input(EXAMPLE_INPUT);
+        let target = std::env::temp_dir().join(format!("day-7-test-{}", std::process::id()));
+        disk::materialize(&fs.root, &target).unwrap();
+        assert_eq!(
+            std::fs::metadata(target.join("d/k")).unwrap().len(),
+            7214296
+        );
+        let transcript = disk::transcript(&target).unwrap();
+        std::fs::remove_dir_all(&target).unwrap();
+        assert!(transcript.starts_with("$ cd /\n$ ls\ndir a\n14848514 b.txt\n"));
+        let replayed = crate::parse_input(&transcript);
+        assert!(replayed.inconsistencies.is_empty());
+        assert_eq!(
+            report::tree(&replayed.root, None),
+            report::tree(&fs.root, None)
+        );
+    }
+
+    #[test]
+    fn disk_rejects_escaping_names() {
+        let base = std::env::temp_dir().join(format!("day-7-escape-{}", std::process::id()));
+        let target = base.join("target");
+        let outside = base.join("outside.txt");
+        let logs = [
+            format!("$ cd /\n$ ls\n5 {}\n", outside.display()),
+            "$ cd /\n$ ls\n5 ../outside.txt\n".to_string(),
+            "$ cd /\n$ ls\ndir ..\n$ cd ..\n".to_string(),
+            "$ cd /\n$ ls\n5 ..\\outside.txt\n".to_string(),
+            "$ cd /\n$ ls\ndir .\n".to_string(),
+            // Valid entries next to an invalid one must not be written either.
+            "$ cd /\n$ ls\n5 ok\ndir a\n5 ../x\n$ cd a\n$ ls\n3 b\n".to_string(),
+            "$ cd /\n$ ls\n5 ok\ndir a\n$ cd a\n$ ls\ndir ..\n".to_string(),
+        ];
+        std::fs::create_dir_all(&target).unwrap();
+        for log in logs {
+            let fs = crate::parse_input(&log);
+            let err = disk::materialize(&fs.root, &target).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput, "{log}");
+            assert!(!outside.exists(), "{log}");
+            assert_eq!(std::fs::read_dir(&target).unwrap().count(), 0, "{log}");
+        }
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let fs = crate::parse_input(EXAMPLE_INPUT);
+        let exported = json::export(&fs.root).unwrap();
+        assert!(exported.contains("\"size\": 48381165"));
+        let imported = json::import(&exported).unwrap();
+        assert_eq!(imported.size(), 48381165);
+        assert_eq!(imported.directories["a"].size(), 94853);
+        assert!(imported.listed);
+        assert_eq!(json::export(&imported).unwrap(), exported);
+        let hand_written = r#"{"name": "/", "files": {"x": 5}, "directories": {}, "size": 1}"#;
+        assert_eq!(json::import(hand_written).unwrap().size(), 5);
+        assert!(json::import("{}").is_err());
+    }
+
+    #[test]
+    fn listings() {
+        let example_input = "$ cd /
+$ ls
+dir a
+dir empty
+1 b
+$ cd a
+$ ls
+$ cd ..
+$ ls
+dir a
+dir empty
+1 b";
+        let fs = crate::parse_input(example_input);
+        assert!(fs.inconsistencies.is_empty());
+        assert!(fs.root.listed);
+        let empty = &fs.root.directories["empty"];
+        assert!(!empty.listed);
+        assert_eq!(empty.size(), 0);
+        assert!(fs.root.directories["a"].listed);
+
+        // A later listing replaces the earlier one, so removed entries no longer count.
+        let example_input = "$ cd /
+$ ls
+dir a
+dir gone
+1 b
+5 c
+$ cd a
+$ ls
+7 d
+$ cd ..
+$ cd gone
+$ ls
+100 e
+$ cd ..
+$ ls
+dir a
+1 b";
+        let fs = crate::parse_input(example_input);
+        assert_eq!(
+            fs.inconsistencies,
+            vec![Inconsistency::ListingChanged {
+                line: 15,
+                path: "/".to_string()
+            }]
+        );
+        assert_eq!(fs.root.size(), 8);
+        assert!(!fs.root.files.contains_key("c"));
+        assert!(!fs.root.directories.contains_key("gone"));
+    }
+
+    #[test]
+    fn undeclared_directory_reported_once() {
+        let example_input = "$ cd /
+$ ls
+dir a
+1 b
+$ cd x
+$ ls
+2 y
+$ cd ..
+$ ls
+dir a
+1 b";
+        let fs = crate::parse_input(example_input);
+        assert_eq!(
+            fs.inconsistencies,
+            vec![Inconsistency::CdIntoUndeclared {
+                line: 5,
+                path: "/x".to_string()
+            }]
+        );
+        // The directory was entered, so it exists and still counts towards the size.
+        assert_eq!(fs.root.size(), 3);
+        assert!(fs.root.directories["x"].listed);
+    }
+
+    #[test]
+    fn inconsistencies() {
+        let example_input = "$ cd /
+$ ls
+dir a
+10 b
+$ cd x
+$ cd ..
+$ ls
+dir a
+12 b
+3 c
+what is this";
+        let fs = crate::parse_input(example_input);
+        assert_eq!(
+            fs.inconsistencies,
+            vec![
+                Inconsistency::CdIntoUndeclared {
+                    line: 5,
+                    path: "/x".to_string()
+                },
+                Inconsistency::FileSizeChanged {
+                    line: 7,
+                    path: "/b".to_string(),
+                    old: 10,
+                    new: 12
+                },
+                Inconsistency::ListingChanged {
+                    line: 7,
+                    path: "/".to_string()
+                },
+                Inconsistency::InvalidLine { line: 11 },
+            ]
+        );
+    }
 }
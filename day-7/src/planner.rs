@@ -0,0 +1,144 @@
+//! Plans which directories to delete to free up enough disk space.
+
+use std::collections::HashMap;
+
+use crate::{Directory, Filesystem};
+
+/// Directories chosen for deletion, together with the space they free up.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Plan {
+    pub paths: Vec<String>,
+    pub freed: u64,
+}
+
+/// Plans deletions on a disk of `disk_size`, so that at least `required` space is unused.
+pub struct DeletionPlanner {
+    pub disk_size: u64,
+    pub required: u64,
+}
+
+impl DeletionPlanner {
+    /// Returns how much space needs to be freed up, or `None` if the disk can never fit the required space.
+    fn space_to_free(&self, fs: &Filesystem) -> Option<u64> {
+        if self.required > self.disk_size {
+            return None;
+        }
+        let unused = self.disk_size.saturating_sub(fs.root.size());
+        Some(self.required.saturating_sub(unused))
+    }
+
+    /// Picks the smallest single directory that frees up enough space.
+    pub fn smallest_directory(&self, fs: &Filesystem) -> Option<Plan> {
+        let target = self.space_to_free(fs)?;
+        if target == 0 {
+            return Some(Plan {
+                paths: Vec::new(),
+                freed: 0,
+            });
+        }
+        fs.find_dirs(|size| size >= target)
+            .into_iter()
+            .min_by_key(|(_, size)| *size)
+            .map(|(path, freed)| Plan {
+                paths: vec![path],
+                freed,
+            })
+    }
+
+    /// Picks a set of directories, none inside another, that frees up enough space
+    /// while deleting as little as possible.
+    ///
+    /// Directories are visited in pre-order, where every directory is directly followed by its subtree.
+    /// From position `i`, the search either deletes directory `i` and continues after its subtree,
+    /// or keeps it and continues with its subdirectories. Branches are cut when they cannot free
+    /// enough space anymore, or cannot beat the best set found so far.
+    ///
+    /// Everything reachable from position `j` is also reachable from any `i < j`, so once a sum has
+    /// been explored from `i`, it never needs exploring again from `i` or later. Only the earliest
+    /// position is remembered for every sum below the target, which keeps memory to the number of
+    /// distinct sums actually visited instead of one bit per byte for every directory.
+    pub fn smallest_set(&self, fs: &Filesystem) -> Option<Plan> {
+        let single = self.smallest_directory(fs)?;
+        let target = self.space_to_free(fs)?;
+        if single.freed == target {
+            return Some(single);
+        }
+        let mut dirs = Vec::new();
+        flatten(&fs.root, "/".to_string(), &mut dirs);
+        // Most space that can be freed from every position onwards.
+        let mut most = vec![0; dirs.len() + 1];
+        for (i, (_, size, end)) in dirs.iter().enumerate().rev() {
+            most[i] = most[i + 1].max(size + most[*end]);
+        }
+        let mut search = Search {
+            dirs: &dirs,
+            most: &most,
+            target,
+            chosen: Vec::new(),
+            best: (single.freed, Vec::new()),
+            explored: HashMap::new(),
+        };
+        search.visit(0, 0);
+        let (freed, chosen) = search.best;
+        if chosen.is_empty() {
+            return Some(single);
+        }
+        let paths = chosen.into_iter().map(|i| dirs[i].0.clone()).collect();
+        Some(Plan { paths, freed })
+    }
+}
+
+/// Depth-first search for the smallest sum of directory sizes reaching the target.
+struct Search<'a> {
+    dirs: &'a [(String, u64, usize)],
+    most: &'a [u64],
+    target: u64,
+    chosen: Vec<usize>,
+    best: (u64, Vec<usize>),
+    /// Earliest position from which every sum has been explored.
+    explored: HashMap<u64, usize>,
+}
+
+impl Search<'_> {
+    fn visit(&mut self, i: usize, freed: u64) {
+        if freed >= self.target {
+            if freed < self.best.0 {
+                self.best = (freed, self.chosen.clone());
+            }
+            return;
+        }
+        // Nothing beats freeing exactly the target.
+        if self.best.0 == self.target || freed + self.most[i] < self.target {
+            return;
+        }
+        if self
+            .explored
+            .get(&freed)
+            .is_some_and(|&earliest| earliest <= i)
+        {
+            return;
+        }
+        let (_, size, end) = self.dirs[i];
+        if freed + size < self.best.0 {
+            self.chosen.push(i);
+            self.visit(end, freed + size);
+            self.chosen.pop();
+        }
+        self.visit(i + 1, freed);
+        // Only recorded once done, as the search from `i + 1` above continues with the same sum.
+        self.explored.insert(freed, i);
+    }
+}
+
+/// Lists the path and size of every directory in pre-order, along with the position right after its subtree.
+fn flatten(dir: &Directory, path: String, dirs: &mut Vec<(String, u64, usize)>) {
+    let idx = dirs.len();
+    dirs.push((path.clone(), dir.size(), 0));
+    let mut names: Vec<&String> = dir.directories.keys().collect();
+    names.sort();
+    for name in names {
+        let child = format!("{}/{name}", path.trim_end_matches('/'));
+        flatten(&dir.directories[name], child, dirs);
+    }
+    dirs[idx].2 = dirs.len();
+}
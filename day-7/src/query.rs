@@ -0,0 +1,131 @@
+//! Queries on the reconstructed filesystem by path.
+
+use crate::{Directory, Filesystem};
+
+/// A file or directory in the filesystem.
+#[derive(Debug, Clone, Copy)]
+pub enum Entry<'a> {
+    File { size: u64 },
+    Directory(&'a Directory),
+}
+
+impl Entry<'_> {
+    /// Size of the file, or total size of the directory.
+    pub fn size(&self) -> u64 {
+        match self {
+            Entry::File { size } => *size,
+            Entry::Directory(dir) => dir.size(),
+        }
+    }
+}
+
+/// Iterator over all entries of the filesystem with their full path, parents before their children.
+pub struct Walk<'a> {
+    stack: Vec<(String, Entry<'a>)>,
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (String, Entry<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, entry) = self.stack.pop()?;
+        if let Entry::Directory(dir) = entry {
+            let prefix = path.trim_end_matches('/');
+            let mut children: Vec<(String, Entry<'a>)> = dir
+                .directories
+                .iter()
+                .map(|(name, dir)| (format!("{prefix}/{name}"), Entry::Directory(dir)))
+                .chain(
+                    dir.files
+                        .iter()
+                        .map(|(name, &size)| (format!("{prefix}/{name}"), Entry::File { size })),
+                )
+                .collect();
+            // The stack hands out the last child first, so reverse the alphabetical order.
+            children.sort_by(|a, b| b.0.cmp(&a.0));
+            self.stack.extend(children);
+        }
+        Some((path, entry))
+    }
+}
+
+impl Filesystem {
+    /// Looks up the file or directory at an absolute path like `/a/e`.
+    pub fn get(&self, path: &str) -> Option<Entry<'_>> {
+        let mut parts = path.split('/').filter(|part| !part.is_empty()).peekable();
+        let mut dir = &self.root;
+        while let Some(name) = parts.next() {
+            if parts.peek().is_none()
+                && let Some(&size) = dir.files.get(name)
+            {
+                return Some(Entry::File { size });
+            }
+            dir = dir.directories.get(name)?;
+        }
+        Some(Entry::Directory(dir))
+    }
+
+    /// Walks over every file and directory, in alphabetical order of their paths.
+    pub fn walk(&self) -> Walk<'_> {
+        Walk {
+            stack: vec![("/".to_string(), Entry::Directory(&self.root))],
+        }
+    }
+
+    /// Returns all entries whose path matches a glob pattern like `**/*.log`.
+    /// `*` and `?` match any characters within a name, `**` matches any number of directories.
+    pub fn glob(&self, pattern: &str) -> Vec<(String, Entry<'_>)> {
+        let pattern: Vec<&str> = pattern.split('/').filter(|part| !part.is_empty()).collect();
+        self.walk()
+            .filter(|(path, _)| {
+                let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+                glob_matches(&pattern, &parts)
+            })
+            .collect()
+    }
+
+    /// Returns the paths and sizes of the `n` largest files, largest first.
+    pub fn largest_files(&self, n: usize) -> Vec<(String, u64)> {
+        let mut files: Vec<(String, u64)> = self
+            .walk()
+            .filter_map(|(path, entry)| match entry {
+                Entry::File { size } => Some((path, size)),
+                Entry::Directory(_) => None,
+            })
+            .collect();
+        files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        files.truncate(n);
+        files
+    }
+
+    /// Returns the paths and total sizes of all directories whose size matches the predicate.
+    pub fn find_dirs(&self, predicate: impl Fn(u64) -> bool) -> Vec<(String, u64)> {
+        self.walk()
+            .filter_map(|(path, entry)| match entry {
+                Entry::Directory(dir) if predicate(dir.size()) => Some((path, dir.size())),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Matches path components against glob pattern components.
+fn glob_matches(pattern: &[&str], parts: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => parts.is_empty(),
+        Some((&"**", rest)) => (0..=parts.len()).any(|skip| glob_matches(rest, &parts[skip..])),
+        Some((first, rest)) => parts.split_first().is_some_and(|(part, parts)| {
+            name_matches(first.as_bytes(), part.as_bytes()) && glob_matches(rest, parts)
+        }),
+    }
+}
+
+/// Matches a single name against a pattern with `*` and `?` wildcards.
+fn name_matches(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| name_matches(rest, &name[skip..])),
+        Some((b'?', rest)) => !name.is_empty() && name_matches(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && name_matches(rest, &name[1..]),
+    }
+}
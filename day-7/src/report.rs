@@ -0,0 +1,98 @@
+//! Textual reports of a reconstructed directory tree.
+
+use std::fmt::Write;
+
+use crate::Directory;
+
+/// Order of the rows in a `du` report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Alphabetically by path.
+    Name,
+    /// Largest directories first, ties ordered by path.
+    Size,
+}
+
+/// Draws the directory tree in the format of the puzzle description, e.g. `- / (dir)`.
+/// Entries are sorted by name, and only entries up to `max_depth` levels below `dir` are drawn.
+pub fn tree(dir: &Directory, max_depth: Option<usize>) -> String {
+    let mut output = String::new();
+    write_tree(dir, 0, max_depth, &mut output);
+    output
+}
+
+fn write_tree(dir: &Directory, depth: usize, max_depth: Option<usize>, output: &mut String) {
+    let indent = "  ".repeat(depth);
+    writeln!(output, "{indent}- {} (dir)", dir.name).expect("Writing to a String should not fail");
+    if max_depth.is_some_and(|max_depth| depth >= max_depth) {
+        return;
+    }
+    // Directories and files are listed together, in alphabetical order.
+    let mut entries: Vec<(&String, Option<u64>)> = dir
+        .directories
+        .keys()
+        .map(|name| (name, None))
+        .chain(dir.files.iter().map(|(name, &size)| (name, Some(size))))
+        .collect();
+    entries.sort();
+    for (name, size) in entries {
+        match size {
+            Some(size) => writeln!(output, "{indent}  - {name} (file, size={size})")
+                .expect("Writing to a String should not fail"),
+            None => write_tree(&dir.directories[name], depth + 1, max_depth, output),
+        }
+    }
+}
+
+/// Lists the total size of every directory like `du -h`, including directories
+/// up to `max_depth` levels below `dir`.
+pub fn du(dir: &Directory, order: SortOrder, max_depth: Option<usize>) -> String {
+    let mut rows = Vec::new();
+    collect_du_rows(dir, String::new(), 0, max_depth, &mut rows);
+    match order {
+        SortOrder::Name => rows.sort_by(|a, b| a.0.cmp(&b.0)),
+        SortOrder::Size => rows.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0))),
+    }
+    rows.iter()
+        .map(|(path, size)| format!("{}\t{path}\n", human_size(*size)))
+        .collect()
+}
+
+fn collect_du_rows(
+    dir: &Directory,
+    parent: String,
+    depth: usize,
+    max_depth: Option<usize>,
+    rows: &mut Vec<(String, u64)>,
+) {
+    let path = if depth == 0 {
+        dir.name.clone()
+    } else {
+        format!("{}/{}", parent.trim_end_matches('/'), dir.name)
+    };
+    if max_depth.is_none_or(|max_depth| depth < max_depth) {
+        for subdir in dir.directories.values() {
+            collect_du_rows(subdir, path.clone(), depth + 1, max_depth, rows);
+        }
+    }
+    rows.push((path, dir.size()));
+}
+
+/// Formats a size like `du -h`: rounded up to a single decimal below 10, and to whole units above it.
+pub fn human_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+    if size < 1024 {
+        return size.to_string();
+    }
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 10.0 {
+        format!("{:.1}{}", (value * 10.0).ceil() / 10.0, UNITS[unit - 1])
+    } else {
+        format!("{}{}", value.ceil(), UNITS[unit - 1])
+    }
+}
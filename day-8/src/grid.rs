@@ -0,0 +1,311 @@
+//! Dense two-dimensional grid stored in row-major order.
+
+use std::fmt;
+use std::iter;
+use std::ops::{Index, IndexMut};
+
+/// Position in a grid as `(row, column)`.
+pub type Position = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Left,
+    Right,
+    Down,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    /// The four orthogonal directions followed by the four diagonal ones.
+    pub const ALL: [Direction; 8] = [
+        Direction::Up,
+        Direction::Left,
+        Direction::Right,
+        Direction::Down,
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::DownLeft,
+        Direction::DownRight,
+    ];
+
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::Down => Direction::Up,
+            Direction::UpLeft => Direction::DownRight,
+            Direction::UpRight => Direction::DownLeft,
+            Direction::DownLeft => Direction::UpRight,
+            Direction::DownRight => Direction::UpLeft,
+        }
+    }
+
+    /// Row and column offset of one step in this direction.
+    pub fn offset(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+            Direction::Down => (1, 0),
+            Direction::UpLeft => (-1, -1),
+            Direction::UpRight => (-1, 1),
+            Direction::DownLeft => (1, -1),
+            Direction::DownRight => (1, 1),
+        }
+    }
+}
+
+/// Directions in which trees look and can be seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighborhood {
+    /// Up, down, left and right, as in the puzzle.
+    Orthogonal,
+    /// The orthogonal directions and the four diagonals.
+    WithDiagonals,
+}
+
+impl Neighborhood {
+    pub fn from_name(name: &str) -> Option<Neighborhood> {
+        match name {
+            "4" => Some(Neighborhood::Orthogonal),
+            "8" => Some(Neighborhood::WithDiagonals),
+            _ => None,
+        }
+    }
+
+    pub fn directions(self) -> &'static [Direction] {
+        match self {
+            Neighborhood::Orthogonal => &Direction::ALL[..4],
+            Neighborhood::WithDiagonals => &Direction::ALL,
+        }
+    }
+}
+
+/// Line and cell numbers are 1-based, and count blank lines too.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    InvalidCell {
+        line: usize,
+        cell: usize,
+        found: String,
+    },
+    Ragged {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidCell { line, cell, found } => {
+                write!(f, "line {line}, cell {cell}: unexpected {found:?}")
+            }
+            ParseError::Ragged {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {line}: expected {expected} cells like the first row, found {found}"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    rows: usize,
+    cols: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Creates a grid with every cell set to `value`.
+    pub fn new(rows: usize, cols: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            rows,
+            cols,
+            cells: vec![value; rows * cols],
+        }
+    }
+
+    /// Parses one row per line, converting every character with `parse_cell`.
+    pub fn parse(input: &str, parse_cell: impl Fn(char) -> Option<T>) -> Result<Self, ParseError> {
+        Self::parse_rows(
+            input,
+            |line| {
+                line.char_indices()
+                    .map(move |(i, c)| &line[i..i + c.len_utf8()])
+            },
+            |cell| cell.chars().next().and_then(&parse_cell),
+        )
+    }
+
+    /// Parses one row per line, converting every whitespace-separated word with `parse_cell`.
+    pub fn parse_words(
+        input: &str,
+        parse_cell: impl Fn(&str) -> Option<T>,
+    ) -> Result<Self, ParseError> {
+        Self::parse_rows(input, str::split_whitespace, parse_cell)
+    }
+
+    /// Parses the non-blank lines of `input` as rows, which must all have the same number of cells.
+    fn parse_rows<'a, I: Iterator<Item = &'a str>>(
+        input: &'a str,
+        split: impl Fn(&'a str) -> I,
+        parse_cell: impl Fn(&'a str) -> Option<T>,
+    ) -> Result<Self, ParseError> {
+        let mut cells = Vec::new();
+        let mut rows = 0;
+        let mut cols = 0;
+        for (idx, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut found = 0;
+            for (col, cell) in split(line).enumerate() {
+                cells.push(parse_cell(cell).ok_or_else(|| ParseError::InvalidCell {
+                    line: idx + 1,
+                    cell: col + 1,
+                    found: cell.to_string(),
+                })?);
+                found += 1;
+            }
+            if rows == 0 {
+                cols = found;
+            } else if found != cols {
+                return Err(ParseError::Ragged {
+                    line: idx + 1,
+                    expected: cols,
+                    found,
+                });
+            }
+            rows += 1;
+        }
+        Ok(Grid { rows, cols, cells })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn contains(&self, (row, col): Position) -> bool {
+        row < self.rows && col < self.cols
+    }
+
+    pub fn get(&self, pos: Position) -> Option<&T> {
+        self.contains(pos)
+            .then(|| &self.cells[pos.0 * self.cols + pos.1])
+    }
+
+    pub fn get_mut(&mut self, pos: Position) -> Option<&mut T> {
+        if self.contains(pos) {
+            Some(&mut self.cells[pos.0 * self.cols + pos.1])
+        } else {
+            None
+        }
+    }
+
+    /// Position one step away in `direction`, if it is still inside the grid.
+    pub fn step(&self, (row, col): Position, direction: Direction) -> Option<Position> {
+        let (dr, dc) = direction.offset();
+        let pos = (row.checked_add_signed(dr)?, col.checked_add_signed(dc)?);
+        self.contains(pos).then_some(pos)
+    }
+
+    /// All positions, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Position> + use<T> {
+        let cols = self.cols;
+        (0..self.rows).flat_map(move |row| (0..cols).map(move |col| (row, col)))
+    }
+
+    /// All cells, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    /// Cells of one row, from left to right.
+    pub fn row(&self, row: usize) -> impl Iterator<Item = &T> {
+        self.cells[row * self.cols..(row + 1) * self.cols].iter()
+    }
+
+    /// Cells of one column, from top to bottom.
+    pub fn col(&self, col: usize) -> impl Iterator<Item = &T> {
+        assert!(col < self.cols, "Should be a column inside the grid");
+        self.cells[col..].iter().step_by(self.cols)
+    }
+
+    /// Positions directly next to `pos` in the directions of `neighborhood`.
+    pub fn neighbors(
+        &self,
+        pos: Position,
+        neighborhood: Neighborhood,
+    ) -> impl Iterator<Item = Position> + '_ {
+        neighborhood
+            .directions()
+            .iter()
+            .copied()
+            .filter_map(move |direction| self.step(pos, direction))
+    }
+
+    /// Positions and cells from `pos` to the edge of the grid in `direction`, excluding `pos`.
+    pub fn ray(&self, pos: Position, direction: Direction) -> impl Iterator<Item = (Position, &T)> {
+        iter::successors(self.step(pos, direction), move |&pos| {
+            self.step(pos, direction)
+        })
+        .map(|pos| (pos, &self[pos]))
+    }
+
+    /// Every row or column of the grid, each traversed from edge to edge in `direction`.
+    pub fn lines(
+        &self,
+        direction: Direction,
+    ) -> impl Iterator<Item = impl Iterator<Item = (Position, &T)>> {
+        // lines start at the positions with no cell behind them
+        let starts: Vec<Position> = self
+            .positions()
+            .filter(|&pos| self.step(pos, direction.opposite()).is_none())
+            .collect();
+        starts
+            .into_iter()
+            .map(move |start| iter::once((start, &self[start])).chain(self.ray(start, direction)))
+    }
+
+    /// Creates a grid of the same shape by converting every cell.
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            rows: self.rows,
+            cols: self.cols,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Position) -> &T {
+        self.get(pos).expect("Should be a position inside the grid")
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, pos: Position) -> &mut T {
+        self.get_mut(pos)
+            .expect("Should be a position inside the grid")
+    }
+}
@@ -0,0 +1,121 @@
+//! Images of the forest: tree heights, visible trees and scenic scores.
+
+use std::io::{self, Write};
+
+use crate::grid::{Grid, Neighborhood};
+use crate::{scenic, visible_trees};
+
+pub type Color = [u8; 3];
+
+const HIDDEN: Color = [40, 40, 40];
+const VISIBLE: Color = [80, 200, 80];
+const HIGHLIGHT: Color = [255, 0, 0];
+/// Colors of the heatmap gradient, from the lowest to the highest value.
+const GRADIENT: [Color; 4] = [[20, 20, 90], [30, 130, 140], [120, 200, 70], [255, 230, 60]];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Heights,
+    Visibility,
+    Scenic,
+}
+
+impl Layer {
+    pub fn from_name(name: &str) -> Option<Layer> {
+        match name {
+            "heights" => Some(Layer::Heights),
+            "visible" => Some(Layer::Visibility),
+            "scenic" => Some(Layer::Scenic),
+            _ => None,
+        }
+    }
+}
+
+/// Color of `value` on the gradient, where `max` gets the last color.
+fn heat(value: f64, max: f64) -> Color {
+    if max <= 0.0 {
+        return GRADIENT[0];
+    }
+    let t = (value / max).clamp(0.0, 1.0) * (GRADIENT.len() - 1) as f64;
+    let index = (t as usize).min(GRADIENT.len() - 2);
+    let fraction = t - index as f64;
+    let (from, to) = (GRADIENT[index], GRADIENT[index + 1]);
+    std::array::from_fn(|i| {
+        (from[i] as f64 + (to[i] as f64 - from[i] as f64) * fraction).round() as u8
+    })
+}
+
+/// Colors every tree according to `layer`, with the best treehouse location in red.
+pub fn render(grid: &Grid<u64>, layer: Layer) -> Grid<Color> {
+    let scores = scenic::scenic_scores(grid, Neighborhood::Orthogonal);
+    let mut colors = match layer {
+        Layer::Heights => {
+            let min = grid.iter().copied().min().unwrap_or(0);
+            let max = grid.iter().copied().max().unwrap_or(0);
+            grid.map(|&height| heat((height - min) as f64, (max - min) as f64))
+        }
+        Layer::Visibility => {
+            visible_trees(grid, Neighborhood::Orthogonal).map(|&v| if v { VISIBLE } else { HIDDEN })
+        }
+        // scores grow with the product of four distances, so a square root keeps
+        // the few very high scores from making everything else look the same
+        Layer::Scenic => {
+            let max = scores.iter().copied().max().unwrap_or(0) as f64;
+            scores.map(|&score| heat((score as f64).sqrt(), max.sqrt()))
+        }
+    };
+    if let Some((pos, _)) = scenic::best_location(&scores) {
+        colors[pos] = HIGHLIGHT;
+    }
+    colors
+}
+
+/// RGB bytes of the image, drawing every cell as a `scale` by `scale` square.
+fn pixels(colors: &Grid<Color>, scale: usize) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(colors.rows() * colors.cols() * scale * scale * 3);
+    for row in 0..colors.rows() {
+        for _ in 0..scale {
+            for color in colors.row(row) {
+                for _ in 0..scale {
+                    pixels.extend_from_slice(color);
+                }
+            }
+        }
+    }
+    pixels
+}
+
+/// Writes the image as a binary PPM file, flushing `out` so buffered write errors are reported.
+pub fn write_ppm(colors: &Grid<Color>, scale: usize, out: &mut impl Write) -> io::Result<()> {
+    let (width, height) = (colors.cols() * scale, colors.rows() * scale);
+    write!(out, "P6\n{width} {height}\n255\n")?;
+    out.write_all(&pixels(colors, scale))?;
+    out.flush()
+}
+
+/// Writes the image as a PNG file.
+pub fn write_png(
+    colors: &Grid<Color>,
+    scale: usize,
+    out: impl Write,
+) -> Result<(), png::EncodingError> {
+    let (width, height) = (colors.cols() * scale, colors.rows() * scale);
+    let mut encoder = png::Encoder::new(out, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&pixels(colors, scale))?;
+    writer.finish()
+}
+
+/// Draws the image in the terminal with two spaces per tree on a true color background.
+pub fn ansi(colors: &Grid<Color>) -> String {
+    let mut output = String::new();
+    for row in 0..colors.rows() {
+        for [r, g, b] in colors.row(row) {
+            output += &format!("\x1b[48;2;{r};{g};{b}m  ");
+        }
+        output += "\x1b[0m\n";
+    }
+    output
+}
@@ -2,159 +2,422 @@
 
 const INPUT: &str = include_str!("./input.txt");
 
-#[derive(Clone, Copy)]
-enum Direction {
-    Up,
-    Left,
-    Right,
-    Down,
+mod grid;
+mod heatmap;
+mod scenic;
+
+use grid::{Direction, Grid, Neighborhood, ParseError, Position};
+
+/// How tree heights are written in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HeightFormat {
+    /// One digit per tree, as in the puzzle.
+    Digits,
+    /// Integers of any size, separated by whitespace.
+    Integers,
 }
 
-fn parse_input(input: &str) -> Vec<Vec<u32>> {
-    // tree heights are increased by 1 to simplify comparison with u32 type
-    input
-        .lines()
-        .map(|line| line.chars().map(|c| c.to_digit(10).unwrap() + 1).collect())
-        .collect()
+fn parse_input(input: &str, format: HeightFormat) -> Result<Grid<u64>, ParseError> {
+    match format {
+        HeightFormat::Digits => Grid::parse(input, |c| c.to_digit(10).map(u64::from)),
+        HeightFormat::Integers => Grid::parse_words(input, |word| word.parse().ok()),
+    }
 }
 
-fn viewing_distance(grid: &[Vec<u32>], row: usize, col: usize, direction: Direction) -> usize {
-    let tree_height = grid[row][col];
+fn viewing_distance(grid: &Grid<u64>, pos: Position, direction: Direction) -> usize {
+    let tree_height = grid[pos];
     let mut distance = 0;
-    match direction {
-        Direction::Up => {
-            for r in (0..row).rev() {
-                distance += 1;
-                if grid[r][col] >= tree_height {
-                    break;
-                }
-            }
+    for (_, &height) in grid.ray(pos, direction) {
+        distance += 1;
+        if height >= tree_height {
+            break;
         }
-        Direction::Down => {
-            for r in (row + 1)..grid.len() {
-                distance += 1;
-                if grid[r][col] >= tree_height {
-                    break;
-                }
-            }
-        }
-        Direction::Left => {
-            for c in (0..col).rev() {
-                distance += 1;
-                if grid[row][c] >= tree_height {
-                    break;
-                }
-            }
-        }
-        Direction::Right => {
-            for c in (col + 1)..grid[0].len() {
-                distance += 1;
-                if grid[row][c] >= tree_height {
-                    break;
+    }
+    distance
+}
+
+/// Marks every tree that can be seen from outside the grid in one of the directions of `neighborhood`.
+fn visible_trees(grid: &Grid<u64>, neighborhood: Neighborhood) -> Grid<bool> {
+    let mut is_visible = Grid::new(grid.rows(), grid.cols(), false);
+    // looking into the grid from one edge, a tree is visible if it is taller than all trees before it
+    for &direction in neighborhood.directions() {
+        for line in grid.lines(direction) {
+            let mut max_height = None;
+            for (pos, &height) in line {
+                if max_height.is_none_or(|max| height > max) {
+                    is_visible[pos] = true;
+                    max_height = Some(height);
                 }
             }
         }
     }
-    distance
+    is_visible
 }
 
-fn solve_part_one(input: &str) -> usize {
-    let grid = parse_input(input);
-    let rows = grid.len();
-    let cols = grid[0].len();
-    let mut is_visible = vec![vec![false; cols]; rows];
-
-    for r in 0..rows {
-        // visible from left check
-        let mut max_height = 0;
-        for c in 0..cols {
-            let height = grid[r][c];
-            if height > max_height {
-                is_visible[r][c] = true;
-                max_height = height;
-            }
+fn count_visible(grid: &Grid<u64>, neighborhood: Neighborhood) -> usize {
+    visible_trees(grid, neighborhood)
+        .iter()
+        .filter(|&&v| v)
+        .count()
+}
+
+fn best_scenic_score(grid: &Grid<u64>, neighborhood: Neighborhood) -> usize {
+    let scores = scenic::scenic_scores(grid, neighborhood);
+    scenic::best_location(&scores).map_or(0, |(_, score)| score)
+}
+
+fn solve_part_one(grid: &Grid<u64>) -> usize {
+    count_visible(grid, Neighborhood::Orthogonal)
+}
+
+fn solve_part_two(grid: &Grid<u64>) -> usize {
+    best_scenic_score(grid, Neighborhood::Orthogonal)
+}
+
+fn main() {
+    // Heights are single digits, unless `--integers` is given anywhere in the arguments.
+    let (flags, args): (Vec<String>, Vec<String>) =
+        std::env::args().partition(|arg| arg == "--integers");
+    let format = if flags.is_empty() {
+        HeightFormat::Digits
+    } else {
+        HeightFormat::Integers
+    };
+    let grid = match parse_input(INPUT, format) {
+        Ok(grid) => grid,
+        Err(err) => {
+            println!("Invalid input: {err}");
+            return;
         }
-        // visible from right check
-        let mut max_height = 0;
-        for c in (0..cols).rev() {
-            let height = grid[r][c];
-            if height > max_height {
-                is_visible[r][c] = true;
-                max_height = height;
+    };
+    let part_one_answer = solve_part_one(&grid);
+    println!("Part one:\n{part_one_answer}");
+    let part_two_answer = solve_part_two(&grid);
+    println!("Part two:\n{part_two_answer}");
+    // Optionally show which trees are visible, the view from the tree at a given row and column,
+    // both answers when looking in `4` or `8` directions, or a heatmap of `heights`,
+    // `visible` trees or `scenic` scores in the terminal or as an image.
+    let neighborhood_argument = |position: usize, default: Neighborhood| {
+        args.get(position).map_or(default, |name| {
+            Neighborhood::from_name(name).expect("Neighborhood should be 4 or 8")
+        })
+    };
+    match args.get(1).map(String::as_str) {
+        Some("visible") => {
+            let mask =
+                visible_trees(&grid, Neighborhood::Orthogonal).map(|&v| if v { '#' } else { '.' });
+            for row in 0..mask.rows() {
+                println!("{}", mask.row(row).collect::<String>());
             }
         }
-    }
-
-    for c in 0..cols {
-        // visible from top check
-        let mut max_height = 0;
-        for r in 0..rows {
-            let height = grid[r][c];
-            if height > max_height {
-                is_visible[r][c] = true;
-                max_height = height;
+        Some("tree") => {
+            let coordinate = |position: usize| -> usize {
+                args.get(position)
+                    .expect("Should be given a row and a column")
+                    .parse()
+                    .expect("Coordinates should be numbers")
+            };
+            let pos = (coordinate(2), coordinate(3));
+            let Some(&height) = grid.get(pos) else {
+                println!("{pos:?} is outside the grid");
+                return;
+            };
+            let neighborhood = neighborhood_argument(4, Neighborhood::Orthogonal);
+            let neighbors: Vec<u64> = grid
+                .neighbors(pos, neighborhood)
+                .map(|pos| grid[pos])
+                .collect();
+            println!("Height {height}, neighbors {neighbors:?}");
+            let heights = |cells: &mut dyn Iterator<Item = &u64>| {
+                cells.map(u64::to_string).collect::<Vec<_>>().join(" ")
+            };
+            println!("Row {}: {}", pos.0, heights(&mut grid.row(pos.0)));
+            println!("Column {}: {}", pos.1, heights(&mut grid.col(pos.1)));
+            for &direction in neighborhood.directions() {
+                let distance = viewing_distance(&grid, pos, direction);
+                println!("{direction:?}: sees {distance} trees");
             }
         }
-        // visible from bottom check
-        let mut max_height = 0;
-        for r in (0..rows).rev() {
-            let height = grid[r][c];
-            if height > max_height {
-                is_visible[r][c] = true;
-                max_height = height;
+        Some("neighborhood") => {
+            let neighborhood = neighborhood_argument(2, Neighborhood::WithDiagonals);
+            let visible = count_visible(&grid, neighborhood);
+            println!("Visible trees:\n{visible}");
+            let scores = scenic::scenic_scores(&grid, neighborhood);
+            if let Some((pos, score)) = scenic::best_location(&scores) {
+                println!("Best scenic score:\n{score} at {pos:?}");
             }
         }
-    }
-    // count total number of visible trees
-    is_visible.iter().flatten().filter(|&&v| v).count()
-}
-
-fn solve_part_two(input: &str) -> usize {
-    let grid = parse_input(input);
-    let mut max_scenic_score = 0;
-
-    for r in 1..(grid.len() - 1) {
-        for c in 1..(grid[0].len() - 1) {
-            let current_scenic_score = viewing_distance(&grid, r, c, Direction::Up)
-                * viewing_distance(&grid, r, c, Direction::Down)
-                * viewing_distance(&grid, r, c, Direction::Left)
-                * viewing_distance(&grid, r, c, Direction::Right);
-            if current_scenic_score > max_scenic_score {
-                max_scenic_score = current_scenic_score;
+        Some("heatmap") => {
+            let layer = args
+                .get(2)
+                .map(|name| {
+                    heatmap::Layer::from_name(name)
+                        .expect("Layer should be heights, visible or scenic")
+                })
+                .unwrap_or(heatmap::Layer::Scenic);
+            let colors = heatmap::render(&grid, layer);
+            let Some(path) = args.get(3) else {
+                print!("{}", heatmap::ansi(&colors));
+                return;
+            };
+            let scale = args
+                .get(4)
+                .map_or(4, |scale| scale.parse().expect("Scale should be a number"));
+            let written = std::fs::File::create(path)
+                .map_err(|err| err.to_string())
+                .and_then(|file| {
+                    let mut out = std::io::BufWriter::new(file);
+                    if path.ends_with(".png") {
+                        heatmap::write_png(&colors, scale, out).map_err(|err| err.to_string())
+                    } else {
+                        heatmap::write_ppm(&colors, scale, &mut out).map_err(|err| err.to_string())
+                    }
+                });
+            if let Err(err) = written {
+                println!("Could not write {path}: {err}");
             }
         }
+        _ => {}
     }
-    max_scenic_score
-}
-
-fn main() {
-    let part_one_answer = solve_part_one(INPUT);
-    println!("Part one:\n{part_one_answer}");
-    let part_two_answer = solve_part_two(INPUT);
-    println!("Part two:\n{part_two_answer}");
 }
 
 #[cfg(test)]
 mod tests {
-    #[test]
-    fn part1() {
-        let example_input = "30373
+    use crate::HeightFormat::{Digits, Integers};
+    use crate::grid::{Direction, Grid, Neighborhood, ParseError};
+    use crate::{heatmap, scenic};
+
+    const EXAMPLE_INPUT: &str = "30373
 25512
 65332
 33549
 35390";
-        let answer = crate::solve_part_one(example_input);
+
+    #[test]
+    fn part1() {
+        let answer = crate::solve_part_one(&crate::parse_input(EXAMPLE_INPUT, Digits).unwrap());
         assert_eq!(answer, 21);
     }
 
     #[test]
     fn part2() {
-        let example_input = "30373
-25512
-65332
-33549
-35390";
-        let answer = crate::solve_part_two(example_input);
+        let answer = crate::solve_part_two(&crate::parse_input(EXAMPLE_INPUT, Digits).unwrap());
         assert_eq!(answer, 8);
     }
+
+    #[test]
+    fn empty_input() {
+        let grid = crate::parse_input("", Digits).unwrap();
+        assert_eq!(crate::solve_part_one(&grid), 0);
+        assert_eq!(crate::solve_part_two(&grid), 0);
+    }
+
+    #[test]
+    fn grid() {
+        let grid = Grid::parse(EXAMPLE_INPUT, |c| c.to_digit(10)).unwrap();
+        assert_eq!((grid.rows(), grid.cols()), (5, 5));
+        assert_eq!(grid[(1, 2)], 5);
+        assert_eq!(grid.get((5, 0)), None);
+        assert_eq!(grid.row(3).copied().collect::<Vec<_>>(), [3, 3, 5, 4, 9]);
+        assert_eq!(grid.col(2).copied().collect::<Vec<_>>(), [3, 5, 3, 5, 3]);
+        let neighbors: Vec<_> = grid.neighbors((0, 0), Neighborhood::Orthogonal).collect();
+        assert_eq!(neighbors, [(0, 1), (1, 0)]);
+        assert_eq!(grid.neighbors((2, 2), Neighborhood::Orthogonal).count(), 4);
+        assert_eq!(
+            grid.neighbors((2, 2), Neighborhood::WithDiagonals).count(),
+            8
+        );
+        assert_eq!(
+            grid.neighbors((0, 4), Neighborhood::WithDiagonals).count(),
+            3
+        );
+        let ray: Vec<_> = grid.ray((2, 2), Direction::Left).collect();
+        assert_eq!(ray, [((2, 1), &5), ((2, 0), &6)]);
+        assert_eq!(grid.ray((0, 3), Direction::Up).count(), 0);
+        let lines: Vec<Vec<_>> = grid
+            .lines(Direction::Up)
+            .map(|line| line.map(|(_, &height)| height).collect())
+            .collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], [3, 3, 6, 2, 3]);
+        let diagonals: Vec<Vec<_>> = grid
+            .lines(Direction::DownRight)
+            .map(|line| line.map(|(pos, _)| pos).collect())
+            .collect();
+        assert_eq!(diagonals.len(), 9);
+        assert_eq!(diagonals[0], [(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)]);
+        assert_eq!(diagonals[4], [(0, 4)]);
+        assert_eq!(diagonals[5], [(1, 0), (2, 1), (3, 2), (4, 3)]);
+        assert_eq!(
+            Grid::parse("12\n3", |c| c.to_digit(10)),
+            Err(ParseError::Ragged {
+                line: 2,
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            Grid::parse("1x", |c| c.to_digit(10)),
+            Err(ParseError::InvalidCell {
+                line: 1,
+                cell: 2,
+                found: "x".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn scenic_scores_match_brute_force() {
+        let grid = crate::parse_input(EXAMPLE_INPUT, Digits).unwrap();
+        let scores = scenic::scenic_scores(&grid, Neighborhood::Orthogonal);
+        assert_eq!(scores[(1, 2)], 4);
+        assert_eq!(scenic::best_location(&scores), Some(((3, 2), 8)));
+        // random grids with few distinct heights, so equal heights block the view often
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        for (rows, cols) in [(1, 1), (1, 7), (6, 1), (9, 13), (20, 20)] {
+            let input: String = (0..rows)
+                .map(|_| {
+                    (0..cols)
+                        .map(|_| {
+                            state ^= state << 13;
+                            state ^= state >> 7;
+                            state ^= state << 17;
+                            char::from(b'0' + (state % 4) as u8)
+                        })
+                        .chain(std::iter::once('\n'))
+                        .collect::<String>()
+                })
+                .collect();
+            let grid = crate::parse_input(&input, Digits).unwrap();
+            for neighborhood in [Neighborhood::Orthogonal, Neighborhood::WithDiagonals] {
+                let scores = scenic::scenic_scores(&grid, neighborhood);
+                for pos in grid.positions() {
+                    let expected: usize = neighborhood
+                        .directions()
+                        .iter()
+                        .map(|&direction| crate::viewing_distance(&grid, pos, direction))
+                        .product();
+                    assert_eq!(scores[pos], expected, "{pos:?} in\n{input}");
+                }
+            }
+        }
+    }
+
+    /// Writer that fails every write, like a disk without free space.
+    struct FullDisk;
+
+    impl std::io::Write for FullDisk {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::StorageFull.into())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn heatmaps() {
+        let grid = crate::parse_input(EXAMPLE_INPUT, Digits).unwrap();
+        let visible = heatmap::render(&grid, heatmap::Layer::Visibility);
+        assert_eq!(visible[(3, 2)], [255, 0, 0]);
+        assert_eq!(visible[(1, 3)], [40, 40, 40]);
+        assert_eq!(visible[(0, 0)], [80, 200, 80]);
+        let heights = heatmap::render(&grid, heatmap::Layer::Heights);
+        assert_eq!(heights[(0, 1)], [20, 20, 90]);
+        assert_eq!(heights[(3, 4)], [255, 230, 60]);
+        let scenic = heatmap::render(&grid, heatmap::Layer::Scenic);
+        assert_eq!(scenic[(0, 0)], [20, 20, 90]);
+        let mut ppm = Vec::new();
+        heatmap::write_ppm(&scenic, 2, &mut ppm).unwrap();
+        assert!(ppm.starts_with(b"P6\n10 10\n255\n"));
+        assert_eq!(ppm.len(), 13 + 10 * 10 * 3);
+        // The whole image fits in the buffer, so the error only shows up when flushing.
+        let mut full = std::io::BufWriter::new(FullDisk);
+        assert!(heatmap::write_ppm(&scenic, 2, &mut full).is_err());
+        let mut png = Vec::new();
+        heatmap::write_png(&scenic, 1, &mut png).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        let ansi = heatmap::ansi(&visible);
+        assert_eq!(ansi.lines().count(), 5);
+        assert!(ansi.contains("\x1b[48;2;255;0;0m  "));
+    }
+
+    #[test]
+    fn integer_heights() {
+        let digits = crate::parse_input(EXAMPLE_INPUT, Digits).unwrap();
+        let spaced: String = EXAMPLE_INPUT
+            .lines()
+            .map(|line| line.chars().map(|c| format!(" {c}")).collect::<String>() + "\n")
+            .collect();
+        assert_eq!(crate::parse_input(&spaced, Integers), Ok(digits.clone()));
+        let scaled: String = EXAMPLE_INPUT
+            .lines()
+            .map(|line| {
+                let heights: Vec<String> = line
+                    .chars()
+                    .map(|c| (c.to_digit(10).unwrap() as u64 * 1_000_000_007).to_string())
+                    .collect();
+                heights.join("\t") + "\n"
+            })
+            .collect();
+        let grid = crate::parse_input(&scaled, Integers).unwrap();
+        assert_eq!(grid[(3, 4)], 9_000_000_063);
+        assert_eq!(crate::solve_part_one(&grid), 21);
+        assert_eq!(crate::solve_part_two(&grid), 8);
+        // trees of height zero are still visible from the edge
+        let flat = crate::parse_input("0 0\n0 0", Integers).unwrap();
+        assert_eq!(crate::solve_part_one(&flat), 4);
+        assert_eq!(
+            crate::parse_input("1 2 3\n\n4 5", Integers),
+            Err(ParseError::Ragged {
+                line: 3,
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            crate::parse_input("1 2\n3 -4", Integers),
+            Err(ParseError::InvalidCell {
+                line: 2,
+                cell: 2,
+                found: "-4".to_string()
+            })
+        );
+        assert!(crate::parse_input("12\n3a", Digits).is_err());
+        // The format is never guessed: a single column of integers is not read as digits.
+        let column = crate::parse_input("10\n20", Integers).unwrap();
+        assert_eq!((column.rows(), column.cols()), (2, 1));
+        assert_eq!(column[(1, 0)], 20);
+        assert_eq!(
+            crate::parse_input("1 2\n3 4", Digits),
+            Err(ParseError::InvalidCell {
+                line: 1,
+                cell: 2,
+                found: " ".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn diagonal_neighborhood() {
+        use crate::{best_scenic_score, count_visible};
+        let grid = crate::parse_input("191\n999\n191", Digits).unwrap();
+        assert_eq!(count_visible(&grid, Neighborhood::Orthogonal), 8);
+        assert_eq!(count_visible(&grid, Neighborhood::WithDiagonals), 9);
+        let grid = crate::parse_input("11111\n11111\n11511\n11111\n11111", Digits).unwrap();
+        assert_eq!(best_scenic_score(&grid, Neighborhood::Orthogonal), 16);
+        assert_eq!(best_scenic_score(&grid, Neighborhood::WithDiagonals), 256);
+        // a tree is visible if it is taller than every tree on one of its rays to the edge
+        let grid = crate::parse_input(EXAMPLE_INPUT, Digits).unwrap();
+        let visible = crate::visible_trees(&grid, Neighborhood::WithDiagonals);
+        for pos in grid.positions() {
+            let expected = Direction::ALL.into_iter().any(|direction| {
+                grid.ray(pos, direction)
+                    .all(|(_, &height)| height < grid[pos])
+            });
+            assert_eq!(visible[pos], expected, "{pos:?}");
+        }
+        assert_eq!(count_visible(&grid, Neighborhood::WithDiagonals), 22);
+    }
 }
@@ -0,0 +1,48 @@
+//! Scenic scores of all trees in linear time.
+
+use crate::grid::{Direction, Grid, Neighborhood, Position};
+
+/// Viewing distance of every tree when looking in `direction`.
+///
+/// Each line is walked from the edge the trees are looking at, keeping a stack of the trees seen
+/// so far whose view is not yet blocked by a tree at least as tall, so every tree is pushed and
+/// popped at most once.
+pub fn viewing_distances(grid: &Grid<u64>, direction: Direction) -> Grid<usize> {
+    let mut distances = Grid::new(grid.rows(), grid.cols(), 0);
+    for line in grid.lines(direction.opposite()) {
+        // (index along the line, height), with non-increasing heights from bottom to top
+        let mut stack: Vec<(usize, u64)> = Vec::new();
+        for (index, (pos, &height)) in line.enumerate() {
+            while stack.last().is_some_and(|&(_, top)| top < height) {
+                stack.pop();
+            }
+            distances[pos] = match stack.last() {
+                Some(&(blocking, _)) => index - blocking,
+                None => index,
+            };
+            stack.push((index, height));
+        }
+    }
+    distances
+}
+
+/// Scenic score of every tree: the product of its viewing distances in the directions of
+/// `neighborhood`.
+pub fn scenic_scores(grid: &Grid<u64>, neighborhood: Neighborhood) -> Grid<usize> {
+    let mut scores = Grid::new(grid.rows(), grid.cols(), 1);
+    for &direction in neighborhood.directions() {
+        let distances = viewing_distances(grid, direction);
+        for pos in grid.positions() {
+            scores[pos] *= distances[pos];
+        }
+    }
+    scores
+}
+
+/// Position and score of the tree with the highest scenic score, the first one in case of a tie.
+pub fn best_location(scores: &Grid<usize>) -> Option<(Position, usize)> {
+    scores
+        .positions()
+        .map(|pos| (pos, scores[pos]))
+        .reduce(|best, current| if current.1 > best.1 { current } else { best })
+}